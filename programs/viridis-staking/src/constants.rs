@@ -1,22 +1,47 @@
 use crate::state::NftApy;
 
 pub const CONFIG_SEED: &[u8] = b"config";
+pub const PENDING_CONFIG_CHANGE_SEED: &[u8] = b"pending_config_change";
 pub const VAULT_SEED: &[u8] = b"vault";
 pub const STAKE_INFO_SEED: &[u8] = b"stake_info";
+pub const STAKE_ENTRY_SEED: &[u8] = b"stake";
+pub const STAKE_COUNTER_SEED: &[u8] = b"stake_counter";
 pub const TOKEN_SEED: &[u8] = b"token";
 pub const NFT_SEED: &[u8] = b"nft";
 pub const NFT_INFO_SEED: &[u8] = b"nft_info";
+pub const CREATOR_ALLOW_LIST_SEED: &[u8] = b"creator_allow_list";
+pub const NFT_COLLECTION_SEED: &[u8] = b"nft_collection";
 
 pub const METADATA_SEED: &[u8] = b"metadata";
 
 pub const DEFAULT_NFT_DAYS_APY: [NftApy; 3] = [
-    NftApy { days: 30, apy: 2950 },
-    NftApy { days: 60, apy: 5950 },
-    NftApy { days: 90, apy: 10450 },
+    NftApy { days: 30, apy: 2950, enabled: true },
+    NftApy { days: 60, apy: 5950, enabled: true },
+    NftApy { days: 90, apy: 10450, enabled: true },
 ];
 
 pub const STAKE_LOCK_DAYS: u16 = 14;
+pub const DAYS_PER_YEAR: u64 = 365;
 pub const BASE_APY: u16 = 550;
 pub const MAX_NFT_REWARD: u64 = 750_000;
 pub const MAX_NFT_APY_DURATION_DAYS: u16 = 90;
+pub const MAX_NFT_TIERS: usize = 10;
+pub const MAX_BASE_APY_TIERS: usize = 8;
+pub const MAX_AMOUNT_BRACKETS: usize = 8;
+pub const MAX_ALLOWED_CREATORS: usize = 8;
+pub const MAX_NFTS_PER_STAKE: usize = 5;
 pub const APY_DECIMALS: u8 = 2;
+pub const REWARD_RATE_DECIMALS: u8 = 9;
+pub const MAX_BPS: u16 = 10_000;
+pub const MAX_BASE_APY: u16 = 10_000;
+pub const MAX_NFT_APY: u16 = 50_000;
+pub const MAX_AMOUNT_BONUS_APY: u16 = 5_000;
+pub const MAX_RARITY_MULTIPLIER_BPS: u16 = 50_000;
+
+pub const CONFIG_VERSION: u8 = 1;
+pub const STAKE_INFO_VERSION: u8 = 1;
+pub const STAKE_ENTRY_VERSION: u8 = 1;
+pub const NFT_INFO_VERSION: u8 = 1;
+pub const PENDING_CONFIG_CHANGE_VERSION: u8 = 1;
+pub const CREATOR_ALLOW_LIST_VERSION: u8 = 1;
+pub const NFT_COLLECTION_VERSION: u8 = 1;
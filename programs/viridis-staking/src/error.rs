@@ -44,4 +44,112 @@ pub enum ErrorCode {
     AlreadyRestaked,
     #[msg("Restake is only allowed before 1/3 of the NFT lock period has passed")]
     RestakeTooLate,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Withdrawal would leave the vault below the outstanding reward liability")]
+    WithdrawExceedsSurplus,
+    #[msg("Reward vault balance is insufficient to pay the reward")]
+    InsufficientVaultBalance,
+    #[msg("Staking is paused")]
+    StakingPaused,
+    #[msg("NFT locking is paused")]
+    NftLockingPaused,
+    #[msg("Claims are paused")]
+    ClaimsPaused,
+    #[msg("Program is not in emergency mode")]
+    NotInEmergencyMode,
+    #[msg("Invalid mint")]
+    InvalidMint,
+    #[msg("Too many NFT lock tiers")]
+    TooManyNftTiers,
+    #[msg("NFT lock tier days must be greater than zero")]
+    InvalidNftTierDays,
+    #[msg("NFT lock tier APY must be greater than zero")]
+    InvalidNftTierApy,
+    #[msg("NFT lock tiers must have unique days in ascending order")]
+    NftTiersNotSorted,
+    #[msg("NFT lock tier already exists")]
+    NftTierAlreadyExists,
+    #[msg("NFT lock tier not found")]
+    NftTierNotFound,
+    #[msg("Too many base APY tiers")]
+    TooManyBaseApyTiers,
+    #[msg("Base APY tiers must have non-zero days and APY with unique days in ascending order")]
+    InvalidBaseApyTiers,
+    #[msg("Too many amount brackets")]
+    TooManyAmountBrackets,
+    #[msg("Amount brackets must have non-zero bonuses with unique minimums in ascending order")]
+    InvalidAmountBrackets,
+    #[msg("Compounding requires the reward mint to match the stake mint")]
+    CompoundUnsupported,
+    #[msg("Partial destake amount must be greater than zero and less than the staked amount")]
+    InvalidPartialAmount,
+    #[msg("Claimable reward must be settled first")]
+    UnsettledReward,
+    #[msg("Penalty must not exceed 10000 basis points")]
+    InvalidPenaltyBps,
+    #[msg("A penalty treasury is required when the vault does not hold the stake mint")]
+    PenaltyTreasuryRequired,
+    #[msg("Invalid penalty destination")]
+    InvalidPenaltyDestination,
+    #[msg("Lock period has ended, use destake instead")]
+    LockPeriodEnded,
+    #[msg("Stake entry must be destaked with its NFT unlocked")]
+    StakeNotSettled,
+    #[msg("Stake info still has stake entries")]
+    StakeInfoNotEmpty,
+    #[msg("Stake token account still holds tokens")]
+    StakeAccountNotEmpty,
+    #[msg("Signer is not authorized")]
+    Unauthorized,
+    #[msg("Account is already on the current layout")]
+    AccountAlreadyMigrated,
+    #[msg("Account data does not match a known layout")]
+    InvalidAccountLayout,
+    #[msg("No admin transfer is pending")]
+    NoPendingAdmin,
+    #[msg("Config changes are timelocked and must be proposed")]
+    ConfigChangeTimelocked,
+    #[msg("Config change delay has not passed yet")]
+    ConfigChangeNotEligible,
+    #[msg("Config change delay must not be negative")]
+    InvalidConfigChangeDelay,
+    #[msg("Base APY must be greater than zero and must not exceed the base APY cap")]
+    InvalidBaseApy,
+    #[msg("Base lock days must be greater than zero")]
+    InvalidBaseLockDays,
+    #[msg("Max NFT APY duration days must be greater than zero")]
+    InvalidMaxNftApyDuration,
+    #[msg("Reward rate must be greater than zero")]
+    InvalidRewardRate,
+    #[msg("NFT lock tiers must not have duplicate days")]
+    DuplicateNftTierDays,
+    #[msg("NFT lock tier APY exceeds the NFT APY cap")]
+    NftTierApyTooHigh,
+    #[msg("NFT lock tier days exceed max NFT APY duration days")]
+    NftTierExceedsMaxDuration,
+    #[msg("Base APY tier APY exceeds the base APY cap")]
+    BaseApyTierTooHigh,
+    #[msg("Base APY tier days must differ from base lock days")]
+    BaseApyTierShadowed,
+    #[msg("Amount bracket bonus APY exceeds the bonus APY cap")]
+    AmountBonusApyTooHigh,
+    #[msg("Too many allowed creators")]
+    TooManyAllowedCreators,
+    #[msg("The primary NFT collection is configured on the config account")]
+    PrimaryNftCollection,
+    #[msg("Rarity proof does not match the committed rarity root")]
+    InvalidRarityProof,
+    #[msg("Rarity multiplier must be between 1x and the rarity multiplier cap")]
+    InvalidRarityMultiplier,
+    #[msg("NFT stack multipliers must be non-zero, at most 1x and non-increasing")]
+    InvalidNftStackMultipliers,
+    #[msg("No more NFTs can be locked in this stake")]
+    NftStackFull,
+    #[msg("Locked NFTs already reach the stacked NFT APY cap")]
+    NftStackCapReached,
+    #[msg("Programmable NFT transfers need the edition, token records and instructions sysvar")]
+    MissingProgrammableNftAccounts,
+    #[msg("Baseline stake infos must be migrated before the vault can be withdrawn from")]
+    LegacyStakesPending,
 }
@@ -0,0 +1,298 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{ AmountBracket, BaseApyTier, Config, NftApy, UpdateConfigArgs };
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigParams {
+    pub admin: Pubkey,
+    pub config_manager: Pubkey,
+    pub treasury_manager: Pubkey,
+    pub pauser: Pubkey,
+    pub base_lock_days: u16,
+    pub max_nft_apy_duration_days: u16,
+    pub base_apy: u16,
+    pub max_nft_reward_lamports: u64,
+    pub nft_days_apy: Vec<NftApy>,
+    pub reward_rate: u64,
+    pub base_apy_tiers: Vec<BaseApyTier>,
+    pub amount_brackets: Vec<AmountBracket>,
+    pub top_up_restarts_lock: bool,
+    pub early_destake_penalty_bps: u16,
+    pub penalty_treasury: Pubkey,
+    pub config_change_delay: i64,
+    pub rarity_root: [u8; 32],
+    pub nft_stack_multipliers_bps: Vec<u16>,
+    pub max_stacked_nft_apy: u16,
+}
+
+impl From<&Config> for ConfigParams {
+    fn from(config: &Config) -> Self {
+        Self {
+            admin: config.admin,
+            config_manager: config.config_manager,
+            treasury_manager: config.treasury_manager,
+            pauser: config.pauser,
+            base_lock_days: config.base_lock_days,
+            max_nft_apy_duration_days: config.max_nft_apy_duration_days,
+            base_apy: config.base_apy,
+            max_nft_reward_lamports: config.max_nft_reward_lamports,
+            nft_days_apy: config.nft_days_apy.clone(),
+            reward_rate: config.reward_rate,
+            base_apy_tiers: config.base_apy_tiers.clone(),
+            amount_brackets: config.amount_brackets.clone(),
+            top_up_restarts_lock: config.top_up_restarts_lock,
+            early_destake_penalty_bps: config.early_destake_penalty_bps,
+            penalty_treasury: config.penalty_treasury,
+            config_change_delay: config.config_change_delay,
+            rarity_root: config.rarity_root,
+            nft_stack_multipliers_bps: config.nft_stack_multipliers_bps.clone(),
+            max_stacked_nft_apy: config.max_stacked_nft_apy,
+        }
+    }
+}
+
+#[event]
+pub struct StakeCreated {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub amount: u64,
+    pub start_time: i64,
+    pub stake_lock_days: u16,
+    pub base_apy: u16,
+}
+
+#[event]
+pub struct NftLocked {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub nft: Pubkey,
+    pub lock_time: i64,
+    pub lock_days: u16,
+    pub apy: u16,
+    pub rarity_multiplier_bps: u16,
+    pub stack_multiplier_bps: u16,
+}
+
+#[event]
+pub struct NftUnlocked {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub nft: Pubkey,
+    pub unlock_time: i64,
+    pub days_locked: u16,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Destaked {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub amount: u64,
+    pub reward: u64,
+    pub destake_time: i64,
+}
+
+#[event]
+pub struct Restaked {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub new_stake_id: u64,
+    pub amount: u64,
+    pub reward: u64,
+    pub restake_time: i64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub authority: Pubkey,
+    pub old: ConfigParams,
+    pub new: ConfigParams,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultFunded {
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultWithdrawn {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseStateUpdated {
+    pub authority: Pubkey,
+    pub staking_paused: bool,
+    pub nft_locking_paused: bool,
+    pub claims_paused: bool,
+    pub emergency_mode: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawn {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub amount: u64,
+    pub forfeited_reward: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NftTiersUpdated {
+    pub authority: Pubkey,
+    pub nft_days_apy: Vec<NftApy>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardCompounded {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub reward: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PartiallyDestaked {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub amount: u64,
+    pub remaining_amount: u64,
+    pub reward: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeIncreased {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub amount: u64,
+    pub total_amount: u64,
+    pub reward: u64,
+    pub lock_restarted: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyDestaked {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub amount: u64,
+    pub penalty: u64,
+    pub penalty_destination: Option<Pubkey>,
+    pub forfeited_reward: u64,
+    pub destake_time: i64,
+}
+
+#[event]
+pub struct StakeEntryClosed {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeEntryMigrated {
+    pub owner: Pubkey,
+    pub stake_id: u64,
+    pub refunded_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeInfoClosed {
+    pub owner: Pubkey,
+    pub next_stake_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeInfoShrunk {
+    pub owner: Pubkey,
+    pub refunded_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RolesUpdated {
+    pub admin: Pubkey,
+    pub config_manager: Pubkey,
+    pub treasury_manager: Pubkey,
+    pub pauser: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeProposed {
+    pub proposer: Pubkey,
+    pub args: UpdateConfigArgs,
+    pub eligible_after: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelled {
+    pub authority: Pubkey,
+    pub args: UpdateConfigArgs,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NftCollectionUpdated {
+    pub authority: Pubkey,
+    pub collection: Pubkey,
+    pub max_nft_reward_lamports: u64,
+    pub max_nft_apy_duration_days: u16,
+    pub nft_days_apy: Vec<NftApy>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NftCollectionRemoved {
+    pub authority: Pubkey,
+    pub collection: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowedCreatorsUpdated {
+    pub authority: Pubkey,
+    pub creators: Vec<Pubkey>,
+    pub timestamp: i64,
+}
@@ -5,40 +5,54 @@ use anchor_spl::token::{ Token, TokenAccount, Mint };
 use crate::constants::*;
 use crate::state::*;
 use crate::error::ErrorCode;
-use crate::utils::{ calculate_claimable_reward, transfer_tokens };
+use crate::events::RewardClaimed;
+use crate::utils::{
+    calculate_claimable_reward,
+    commit_reward_liability,
+    release_reward_liability,
+    transfer_tokens,
+};
 
 #[derive(Accounts)]
-#[instruction(stake_index: u64)]
+#[instruction(stake_id: u64)]
 pub struct Claim<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
     #[account(mut, seeds = [VAULT_SEED], bump)]
     pub token_vault: Account<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
         bump,
     )]
-    pub stake_info: Account<'info, StakeInfo>,
+    pub stake_entry: Account<'info, StakeEntry>,
 
-    #[account(mut, associated_token::mint = mint, associated_token::authority = signer)]
-    pub user_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = signer
+    )]
+    pub user_reward_token: Account<'info, TokenAccount>,
 
-    pub mint: Account<'info, Mint>,
+    #[account(address = config.reward_mint @ ErrorCode::InvalidMint)]
+    pub reward_mint: Account<'info, Mint>,
 
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn claim(ctx: Context<Claim>, stake_index: u64) -> Result<()> {
-    let Claim { token_program, stake_info, token_vault, user_token, .. } = ctx.accounts;
+pub fn claim(ctx: Context<Claim>, stake_id: u64) -> Result<()> {
+    let Claim { token_program, config, stake_entry, token_vault, user_reward_token, .. } =
+        ctx.accounts;
 
-    require!((stake_index as usize) < stake_info.stakes.len(), ErrorCode::InvalidStakeIndex);
+    require!(!config.is_claims_paused(), ErrorCode::ClaimsPaused);
 
-    let stake_entry = &mut stake_info.stakes[stake_index as usize];
     require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
 
     let current_time = Clock::get()?.unix_timestamp;
@@ -46,16 +60,28 @@ pub fn claim(ctx: Context<Claim>, stake_index: u64) -> Result<()> {
     let claimable_reward = calculate_claimable_reward(stake_entry, current_time)?;
 
     if claimable_reward > 0 {
+        require!(token_vault.amount >= claimable_reward, ErrorCode::InsufficientVaultBalance);
+
+        release_reward_liability(config, stake_entry, current_time)?;
         stake_entry.add_payment(claimable_reward);
+        config.add_payment(claimable_reward);
+        commit_reward_liability(config, stake_entry, current_time)?;
 
         transfer_tokens(
             token_vault.to_account_info(),
-            user_token.to_account_info(),
+            user_reward_token.to_account_info(),
             token_vault.to_account_info(),
             claimable_reward,
             token_program.to_account_info(),
             Some(&[&[VAULT_SEED, &[ctx.bumps.token_vault]]])
         )?;
+
+        emit!(RewardClaimed {
+            owner: ctx.accounts.signer.key(),
+            stake_id,
+            amount: claimable_reward,
+            timestamp: current_time,
+        });
     }
 
     Ok(())
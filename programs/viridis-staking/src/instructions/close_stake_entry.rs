@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::events::StakeEntryClosed;
+
+#[derive(Accounts)]
+#[instruction(stake_id: u64)]
+pub struct CloseStakeEntry<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
+        bump,
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
+        bump,
+        close = signer,
+        constraint = stake_entry.is_settled() @ ErrorCode::StakeNotSettled
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+}
+
+/// Closes a destaked entry whose NFT has been unlocked and refunds its rent.
+pub fn close_stake_entry(ctx: Context<CloseStakeEntry>, stake_id: u64) -> Result<()> {
+    ctx.accounts.stake_info.close_stake();
+
+    emit!(StakeEntryClosed {
+        owner: ctx.accounts.signer.key(),
+        stake_id,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{ close_account, CloseAccount, Token, TokenAccount };
+
+use crate::constants::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::events::StakeInfoClosed;
+
+#[derive(Accounts)]
+pub struct CloseStakeInfo<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
+        bump,
+        close = signer,
+        constraint = stake_info.is_empty() @ ErrorCode::StakeInfoNotEmpty
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + StakeCounter::INIT_SPACE,
+        seeds = [STAKE_COUNTER_SEED, signer.key.as_ref()],
+        bump
+    )]
+    pub stake_counter: Account<'info, StakeCounter>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_SEED, signer.key.as_ref()],
+        bump,
+        constraint = stake_account.amount == 0 @ ErrorCode::StakeAccountNotEmpty
+    )]
+    pub stake_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Closes a stake info without open or unmigrated stakes together with its stake token
+/// account, if one was created. The next stake id is kept in the stake counter, so a stake
+/// info opened again later does not reuse ids.
+pub fn close_stake_info(ctx: Context<CloseStakeInfo>) -> Result<()> {
+    let CloseStakeInfo { signer, stake_info, stake_counter, stake_account, token_program, .. } =
+        ctx.accounts;
+
+    stake_counter.next_stake_id = stake_info.next_stake_id;
+
+    if let Some(stake_account) = stake_account {
+        close_account(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                CloseAccount {
+                    account: stake_account.to_account_info(),
+                    destination: signer.to_account_info(),
+                    authority: stake_account.to_account_info(),
+                },
+                &[&[TOKEN_SEED, signer.key.as_ref(), &[ctx.bumps.stake_account.unwrap()]]]
+            )
+        )?;
+    }
+
+    emit!(StakeInfoClosed {
+        owner: signer.key(),
+        next_stake_id: stake_counter.next_stake_id,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
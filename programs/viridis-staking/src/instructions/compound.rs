@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{ Token, TokenAccount };
+
+use crate::constants::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::events::RewardCompounded;
+use crate::utils::{
+    add_principal,
+    calculate_claimable_reward,
+    commit_reward_liability,
+    release_reward_liability,
+    transfer_tokens,
+};
+
+#[derive(Accounts)]
+#[instruction(stake_id: u64)]
+pub struct Compound<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
+        bump,
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_SEED, signer.key.as_ref()],
+        bump,
+    )]
+    pub stake_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn compound(ctx: Context<Compound>, stake_id: u64) -> Result<()> {
+    let Compound { token_program, config, stake_entry, token_vault, stake_account, .. } =
+        ctx.accounts;
+
+    require!(!config.is_staking_paused(), ErrorCode::StakingPaused);
+    require!(!config.is_claims_paused(), ErrorCode::ClaimsPaused);
+    require!(config.reward_mint == config.mint, ErrorCode::CompoundUnsupported);
+
+    require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let claimable_reward = calculate_claimable_reward(stake_entry, current_time)?;
+
+    if claimable_reward > 0 {
+        require!(token_vault.amount >= claimable_reward, ErrorCode::InsufficientVaultBalance);
+
+        release_reward_liability(config, stake_entry, current_time)?;
+        stake_entry.add_payment(claimable_reward);
+        add_principal(stake_entry, claimable_reward, current_time)?;
+
+        config.add_payment(claimable_reward);
+        config.add_stake(claimable_reward)?;
+        commit_reward_liability(config, stake_entry, current_time)?;
+
+        transfer_tokens(
+            token_vault.to_account_info(),
+            stake_account.to_account_info(),
+            token_vault.to_account_info(),
+            claimable_reward,
+            token_program.to_account_info(),
+            Some(&[&[VAULT_SEED, &[ctx.bumps.token_vault]]])
+        )?;
+
+        emit!(RewardCompounded {
+            owner: ctx.accounts.signer.key(),
+            stake_id,
+            reward: claimable_reward,
+            amount: stake_entry.amount,
+            timestamp: current_time,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::events::{ ConfigChangeCancelled, ConfigChangeProposed };
+use crate::utils::validate_config;
+
+use super::apply_config_change;
+
+#[derive(Accounts)]
+pub struct ProposeConfigChange<'info> {
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = config_manager,
+        space = 8 + PendingConfigChange::INIT_SPACE,
+        seeds = [PENDING_CONFIG_CHANGE_SEED],
+        bump
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+
+    #[account(mut, address = config.config_manager @ ErrorCode::Unauthorized)]
+    pub config_manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManagePendingConfigChange<'info> {
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_CONFIG_CHANGE_SEED],
+        bump,
+        close = config_manager
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+
+    #[account(mut, address = config.config_manager @ ErrorCode::Unauthorized)]
+    pub config_manager: Signer<'info>,
+}
+
+/// Queues a config change that can be executed once `config_change_delay` has passed.
+/// Only one change can be pending at a time.
+pub fn propose_config_change(
+    ctx: Context<ProposeConfigChange>,
+    args: UpdateConfigArgs
+) -> Result<()> {
+    let ProposeConfigChange { config, pending_config_change, config_manager, .. } =
+        ctx.accounts;
+
+    // Reject the change now rather than when it becomes executable
+    let mut proposed = (**config).clone();
+    proposed.apply_update(args.clone());
+    validate_config(&proposed)?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let eligible_after = current_time
+        .checked_add(config.config_change_delay)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    pending_config_change.set_inner(PendingConfigChange {
+        version: PENDING_CONFIG_CHANGE_VERSION,
+        proposer: config_manager.key(),
+        args: args.clone(),
+        eligible_after,
+        reserved: [0; 32],
+    });
+
+    emit!(ConfigChangeProposed {
+        proposer: config_manager.key(),
+        args,
+        eligible_after,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
+
+pub fn execute_config_change(ctx: Context<ManagePendingConfigChange>) -> Result<()> {
+    let ManagePendingConfigChange { config, pending_config_change, config_manager } =
+        ctx.accounts;
+
+    require!(
+        Clock::get()?.unix_timestamp >= pending_config_change.eligible_after,
+        ErrorCode::ConfigChangeNotEligible
+    );
+
+    apply_config_change(config, pending_config_change.args.clone(), config_manager.key())
+}
+
+pub fn cancel_config_change(ctx: Context<ManagePendingConfigChange>) -> Result<()> {
+    emit!(ConfigChangeCancelled {
+        authority: ctx.accounts.config_manager.key(),
+        args: ctx.accounts.pending_config_change.args.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -6,22 +6,26 @@ use crate::state::*;
 use crate::utils::*;
 use crate::constants::*;
 use crate::error::ErrorCode;
+use crate::events::Destaked;
 
 #[derive(Accounts)]
-#[instruction(stake_index: u64)]
+#[instruction(stake_id: u64)]
 pub struct Destake<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
     #[account(mut, seeds = [VAULT_SEED], bump)]
     pub token_vault: Account<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
         bump,
     )]
-    pub stake_info: Account<'info, StakeInfo>,
+    pub stake_entry: Account<'info, StakeEntry>,
 
     #[account(
         mut,
@@ -33,50 +37,58 @@ pub struct Destake<'info> {
     #[account(mut, associated_token::mint = mint, associated_token::authority = signer)]
     pub user_token: Account<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = signer
+    )]
+    pub user_reward_token: Account<'info, TokenAccount>,
+
+    #[account(address = config.mint @ ErrorCode::InvalidMint)]
     pub mint: Account<'info, Mint>,
 
+    #[account(address = config.reward_mint @ ErrorCode::InvalidMint)]
+    pub reward_mint: Account<'info, Mint>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn destake(ctx: Context<Destake>, stake_index: u64) -> Result<()> {
-    let Destake { token_program, stake_info, token_vault, user_token, stake_account, .. } =
-        ctx.accounts;
-
-    require!((stake_index as usize) < stake_info.stakes.len(), ErrorCode::InvalidStakeIndex);
-
-    let stake_entry = &mut stake_info.stakes[stake_index as usize];
+pub fn destake(ctx: Context<Destake>, stake_id: u64) -> Result<()> {
+    let Destake {
+        token_program,
+        config,
+        stake_entry,
+        token_vault,
+        user_token,
+        user_reward_token,
+        stake_account,
+        ..
+    } = ctx.accounts;
+
+    require!(!config.is_claims_paused(), ErrorCode::ClaimsPaused);
     require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
 
     let current_time = Clock::get()?.unix_timestamp;
     stake_entry.destake_time = Some(current_time);
 
-    let base_days_passed = calculate_days_passed(stake_entry.start_time, current_time);
-    require!(
-        base_days_passed >= (stake_entry.stake_lock_days as i64),
-        ErrorCode::BaseLockPeriodNotEnded
-    );
-
-    if
-        let (Some(nft_lock_time), Some(nft_lock_days)) = (
-            stake_entry.nft_lock_time,
-            stake_entry.nft_lock_days,
-        )
-    {
-        let nft_days_passed = calculate_days_passed(nft_lock_time, current_time);
-
-        require!(nft_days_passed >= (nft_lock_days as i64), ErrorCode::NftLockPeriodNotEnded);
-    }
+    check_lock_periods_ended(stake_entry, current_time)?;
+
+    release_reward_liability(config, stake_entry, current_time)?;
+    config.remove_stake(stake_entry.amount);
 
     let claimable_reward = calculate_claimable_reward(stake_entry, current_time)?;
 
     if claimable_reward > 0 {
+        require!(token_vault.amount >= claimable_reward, ErrorCode::InsufficientVaultBalance);
+
         stake_entry.add_payment(claimable_reward);
+        config.add_payment(claimable_reward);
 
         transfer_tokens(
             token_vault.to_account_info(),
-            user_token.to_account_info(),
+            user_reward_token.to_account_info(),
             token_vault.to_account_info(),
             claimable_reward,
             token_program.to_account_info(),
@@ -93,5 +105,13 @@ pub fn destake(ctx: Context<Destake>, stake_index: u64) -> Result<()> {
         Some(&[&[TOKEN_SEED, ctx.accounts.signer.key.as_ref(), &[ctx.bumps.stake_account]]])
     )?;
 
+    emit!(Destaked {
+        owner: ctx.accounts.signer.key(),
+        stake_id,
+        amount: stake_entry.amount,
+        reward: claimable_reward,
+        destake_time: current_time,
+    });
+
     Ok(())
 }
@@ -0,0 +1,136 @@
+use anchor_spl::token::{ Token, TokenAccount, Mint };
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::utils::*;
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::events::EarlyDestaked;
+
+#[derive(Accounts)]
+#[instruction(stake_id: u64)]
+pub struct EarlyDestake<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(seeds = [VAULT_SEED], bump)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
+        bump,
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_SEED, signer.key.as_ref()],
+        bump,
+    )]
+    pub stake_account: Account<'info, TokenAccount>,
+
+    #[account(mut, associated_token::mint = mint, associated_token::authority = signer)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    // Only required when a penalty is charged, since without a treasury the vault can only
+    // receive it when it holds the stake mint
+    #[account(
+        mut,
+        address = config.penalty_destination(token_vault.key())
+            @ ErrorCode::InvalidPenaltyDestination,
+        token::mint = mint
+    )]
+    pub penalty_destination: Option<Account<'info, TokenAccount>>,
+
+    #[account(address = config.mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Returns the principal of a stake before its lock ends, forfeiting all unpaid rewards and
+/// charging `early_destake_penalty_bps` of the principal. A locked NFT is released by the
+/// destake and can then be retrieved with `unlock_nft`.
+pub fn early_destake(ctx: Context<EarlyDestake>, stake_id: u64) -> Result<()> {
+    let EarlyDestake {
+        token_program,
+        config,
+        stake_entry,
+        user_token,
+        stake_account,
+        penalty_destination,
+        ..
+    } = ctx.accounts;
+
+    require!(!config.is_claims_paused(), ErrorCode::ClaimsPaused);
+    require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        check_lock_periods_ended(stake_entry, current_time).is_err(),
+        ErrorCode::LockPeriodEnded
+    );
+
+    let forfeited_reward = calculate_claimable_reward(stake_entry, current_time)?;
+
+    // The NFT lock ends together with the stake, so `unlock_nft` only counts the days it
+    // was actually locked for towards `NftInfo`
+    stake_entry.destake_time = Some(current_time);
+
+    release_reward_liability(config, stake_entry, current_time)?;
+    config.remove_stake(stake_entry.amount);
+
+    let penalty = calculate_penalty(
+        stake_entry.amount,
+        config.early_destake_penalty_bps
+    ).ok_or(ErrorCode::MathOverflow)?;
+    let amount = stake_entry.amount - penalty;
+
+    let signer_key = ctx.accounts.signer.key();
+    let stake_account_seeds: &[&[&[u8]]] = &[
+        &[TOKEN_SEED, signer_key.as_ref(), &[ctx.bumps.stake_account]],
+    ];
+
+    if penalty > 0 {
+        let penalty_destination = penalty_destination
+            .as_ref()
+            .ok_or(ErrorCode::InvalidPenaltyDestination)?;
+
+        transfer_tokens(
+            stake_account.to_account_info(),
+            penalty_destination.to_account_info(),
+            stake_account.to_account_info(),
+            penalty,
+            token_program.to_account_info(),
+            Some(stake_account_seeds)
+        )?;
+    }
+
+    transfer_tokens(
+        stake_account.to_account_info(),
+        user_token.to_account_info(),
+        stake_account.to_account_info(),
+        amount,
+        token_program.to_account_info(),
+        Some(stake_account_seeds)
+    )?;
+
+    emit!(EarlyDestaked {
+        owner: signer_key,
+        stake_id,
+        amount,
+        penalty,
+        penalty_destination: penalty_destination.as_ref().map(|account| account.key()),
+        forfeited_reward,
+        destake_time: current_time,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anchor_spl::token::{ Token, TokenAccount, Mint };
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::utils::*;
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::events::EmergencyWithdrawn;
+
+#[derive(Accounts)]
+#[instruction(stake_id: u64)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
+        bump,
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_SEED, signer.key.as_ref()],
+        bump,
+    )]
+    pub stake_account: Account<'info, TokenAccount>,
+
+    #[account(mut, associated_token::mint = mint, associated_token::authority = signer)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(address = config.mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Returns the principal of a stake regardless of its lock, forfeiting all unpaid rewards.
+/// Locked NFTs are not moved; they stay in their lock accounts under the now destaked stake.
+pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>, stake_id: u64) -> Result<()> {
+    let EmergencyWithdraw { token_program, config, stake_entry, user_token, stake_account, .. } =
+        ctx.accounts;
+
+    require!(config.emergency_mode, ErrorCode::NotInEmergencyMode);
+    require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    let forfeited_reward = calculate_claimable_reward(stake_entry, current_time)?;
+
+    stake_entry.destake_time = Some(current_time);
+
+    release_reward_liability(config, stake_entry, current_time)?;
+    config.remove_stake(stake_entry.amount);
+
+    transfer_tokens(
+        stake_account.to_account_info(),
+        user_token.to_account_info(),
+        stake_account.to_account_info(),
+        stake_entry.amount,
+        token_program.to_account_info(),
+        Some(&[&[TOKEN_SEED, ctx.accounts.signer.key.as_ref(), &[ctx.bumps.stake_account]]])
+    )?;
+
+    emit!(EmergencyWithdrawn {
+        owner: ctx.accounts.signer.key(),
+        stake_id,
+        amount: stake_entry.amount,
+        forfeited_reward,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{ associated_token::AssociatedToken, token::{ Mint, Token, TokenAccount } };
+use crate::utils::transfer_tokens;
+use crate::{ constants::*, error::ErrorCode, events::VaultFunded, state::* };
+
+#[derive(Accounts)]
+pub struct FundVault<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [VAULT_SEED], bump, token::mint = reward_mint)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, associated_token::mint = reward_mint, associated_token::authority = signer)]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(address = config.reward_mint @ ErrorCode::InvalidMint)]
+    pub reward_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn fund_vault(ctx: Context<FundVault>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let FundVault { signer, token_vault, user_token, token_program, .. } = ctx.accounts;
+
+    transfer_tokens(
+        user_token.to_account_info(),
+        token_vault.to_account_info(),
+        signer.to_account_info(),
+        amount,
+        token_program.to_account_info(),
+        None
+    )?;
+
+    emit!(VaultFunded {
+        funder: signer.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
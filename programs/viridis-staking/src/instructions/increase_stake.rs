@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{ Mint, Token, TokenAccount };
+
+use crate::constants::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::events::StakeIncreased;
+use crate::utils::{
+    add_principal,
+    calculate_claimable_reward,
+    commit_reward_liability,
+    release_reward_liability,
+    restart_lock,
+    transfer_tokens,
+};
+
+#[derive(Accounts)]
+#[instruction(stake_id: u64)]
+pub struct IncreaseStake<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [VAULT_SEED], bump)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
+        bump,
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_SEED, signer.key.as_ref()],
+        bump,
+    )]
+    pub stake_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = signer
+    )]
+    pub user_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = signer
+    )]
+    pub user_reward_token: Account<'info, TokenAccount>,
+
+    #[account(address = config.mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(address = config.reward_mint @ ErrorCode::InvalidMint)]
+    pub reward_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn increase_stake(ctx: Context<IncreaseStake>, stake_id: u64, amount: u64) -> Result<()> {
+    let IncreaseStake {
+        signer,
+        token_program,
+        config,
+        stake_entry,
+        token_vault,
+        stake_account,
+        user_token,
+        user_reward_token,
+        ..
+    } = ctx.accounts;
+
+    require!(amount > 0, ErrorCode::NoTokens);
+    require!(!config.is_staking_paused(), ErrorCode::StakingPaused);
+    require!(!config.is_claims_paused(), ErrorCode::ClaimsPaused);
+
+    require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    release_reward_liability(config, stake_entry, current_time)?;
+
+    let claimable_reward = calculate_claimable_reward(stake_entry, current_time)?;
+
+    if claimable_reward > 0 {
+        require!(token_vault.amount >= claimable_reward, ErrorCode::InsufficientVaultBalance);
+
+        stake_entry.add_payment(claimable_reward);
+        config.add_payment(claimable_reward);
+
+        transfer_tokens(
+            token_vault.to_account_info(),
+            user_reward_token.to_account_info(),
+            token_vault.to_account_info(),
+            claimable_reward,
+            token_program.to_account_info(),
+            Some(&[&[VAULT_SEED, &[ctx.bumps.token_vault]]])
+        )?;
+    }
+
+    let lock_restarted = config.top_up_restarts_lock;
+    if lock_restarted {
+        restart_lock(stake_entry, current_time)?;
+    }
+
+    add_principal(stake_entry, amount, current_time)?;
+
+    config.add_stake(amount)?;
+    commit_reward_liability(config, stake_entry, current_time)?;
+
+    transfer_tokens(
+        user_token.to_account_info(),
+        stake_account.to_account_info(),
+        signer.to_account_info(),
+        amount,
+        token_program.to_account_info(),
+        None
+    )?;
+
+    emit!(StakeIncreased {
+        owner: signer.key(),
+        stake_id,
+        amount,
+        total_amount: stake_entry.amount,
+        reward: claimable_reward,
+        lock_restarted,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
@@ -3,14 +3,14 @@ use anchor_spl::metadata::Metadata;
 use anchor_spl::token::{ Mint, Token, TokenAccount };
 use crate::constants::*;
 use crate::state::Config;
-use crate::utils::to_lamports;
+use crate::utils::{ default_reward_rate, to_lamports };
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
-    #[account(init, seeds = [CONFIG_SEED], bump, payer = signer, space = Config::len())]
+    #[account(init, seeds = [CONFIG_SEED], bump, payer = signer, space = 8 + Config::INIT_SPACE)]
     pub config: Account<'info, Config>,
 
     #[account(
@@ -18,12 +18,13 @@ pub struct Initialize<'info> {
         seeds = [VAULT_SEED],
         bump,
         payer = signer,
-        token::mint = mint,
+        token::mint = reward_mint,
         token::authority = token_vault_account
     )]
     pub token_vault_account: Account<'info, TokenAccount>,
 
     pub mint: Account<'info, Mint>,
+    pub reward_mint: Account<'info, Mint>,
     /// CHECK: NFT collection used to verify NFTs
     pub nft_collection: AccountInfo<'info>,
     pub token_program: Program<'info, Token>,
@@ -32,15 +33,24 @@ pub struct Initialize<'info> {
 }
 
 pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-    let Initialize { config, signer, nft_collection, mint, .. } = ctx.accounts;
+    let Initialize { config, signer, nft_collection, mint, reward_mint, .. } = ctx.accounts;
 
+    config.version = CONFIG_VERSION;
     config.admin = signer.key();
+    config.config_manager = signer.key();
+    config.treasury_manager = signer.key();
+    config.pauser = signer.key();
+    config.mint = mint.key();
+    config.mint_decimals = mint.decimals;
+    config.reward_mint = reward_mint.key();
+    config.reward_mint_decimals = reward_mint.decimals;
+    config.reward_rate = default_reward_rate(mint.decimals, reward_mint.decimals)?;
     config.nft_collection = nft_collection.key();
     config.max_nft_reward_lamports = to_lamports(MAX_NFT_REWARD, mint.decimals)?;
     config.max_nft_apy_duration_days = MAX_NFT_APY_DURATION_DAYS;
     config.base_lock_days = STAKE_LOCK_DAYS;
     config.base_apy = BASE_APY;
-    config.nft_days_apy = DEFAULT_NFT_DAYS_APY;
+    config.nft_days_apy = DEFAULT_NFT_DAYS_APY.to_vec();
 
     Ok(())
 }
@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::{ constants::*, state::StakeInfo };
+use crate::{ constants::*, state::{ StakeCounter, StakeInfo } };
 
 #[derive(Accounts)]
 pub struct InitializeStakeInfo<'info> {
@@ -10,17 +10,30 @@ pub struct InitializeStakeInfo<'info> {
     #[account(
         init,
         payer = signer,
-        space = 8 + 32 + 4,
+        space = 8 + StakeInfo::INIT_SPACE,
         seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
         bump
     )]
     pub stake_info: Account<'info, StakeInfo>,
 
+    /// CHECK: only exists, and is then read, if the signer closed a stake info before
+    #[account(seeds = [STAKE_COUNTER_SEED, signer.key.as_ref()], bump)]
+    pub stake_counter: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
 pub fn initialize_stake_info(ctx: Context<InitializeStakeInfo>) -> Result<()> {
-    let stake_info = &mut ctx.accounts.stake_info;
-    stake_info.address = ctx.accounts.signer.key();
+    let InitializeStakeInfo { signer, stake_info, stake_counter, .. } = ctx.accounts;
+
+    stake_info.version = STAKE_INFO_VERSION;
+    stake_info.address = signer.key();
+
+    // Stake ids carry on from the stake info the signer closed
+    if stake_counter.owner == &crate::ID {
+        let data = stake_counter.try_borrow_data()?;
+        stake_info.next_stake_id = StakeCounter::try_deserialize(&mut &data[..])?.next_stake_id;
+    }
+
     Ok(())
 }
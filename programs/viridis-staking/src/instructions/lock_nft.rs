@@ -1,28 +1,46 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{ metadata::{ Metadata, MetadataAccount }, token::{ Mint, Token, TokenAccount } };
-use crate::utils::{ get_apy, transfer_tokens };
-use crate::{ constants::*, error::ErrorCode, state::* };
+use anchor_lang::{ prelude::*, solana_program::sysvar };
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{ Metadata, MetadataAccount },
+    token::{ Mint, Token, TokenAccount },
+};
+use crate::utils::{
+    cap_stacked_nft_apy,
+    commit_reward_liability,
+    get_apy,
+    is_programmable_nft,
+    programmable_nft_account,
+    release_reward_liability,
+    resolve_rarity_multiplier,
+    scale_apy,
+    scale_nft_reward_cap,
+    transfer_programmable_nft,
+    transfer_tokens,
+    verify_nft_eligibility,
+    ProgrammableNftTransfer,
+};
+use crate::{ constants::*, error::ErrorCode, events::NftLocked, state::* };
 
 #[derive(Accounts)]
-#[instruction(stake_index: u64)]
+#[instruction(stake_id: u64)]
 pub struct LockNft<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
-    #[account(seeds = [CONFIG_SEED], bump)]
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
     pub config: Box<Account<'info, Config>>,
 
     #[account(
         mut,
-        seeds = [STAKE_INFO_SEED, signer.key().as_ref()],
+        seeds = [STAKE_ENTRY_SEED, signer.key().as_ref(), &stake_id.to_le_bytes()],
         bump,
     )]
-    pub stake_info: Box<Account<'info, StakeInfo>>,
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
 
     #[account(
         init_if_needed,
         payer = signer,
-        space = 8 + 2,
+        space = 8 + NftInfo::INIT_SPACE,
         seeds = [NFT_INFO_SEED, mint.key().as_ref()],
         bump
     )]
@@ -46,63 +64,190 @@ pub struct LockNft<'info> {
     pub user_nft_account: Box<Account<'info, TokenAccount>>,
 
     #[account(
+        mut,
         seeds = [METADATA_SEED, token_metadata_program.key().as_ref(), mint.key().as_ref()],
         seeds::program = token_metadata_program.key(),
         bump
     )]
     pub metadata: Box<Account<'info, MetadataAccount>>,
 
+    #[account(
+        constraint = mint.supply == 1 && mint.decimals == 0 @ ErrorCode::InvalidNftMint
+    )]
     pub mint: Box<Account<'info, Mint>>,
 
+    #[account(seeds = [CREATOR_ALLOW_LIST_SEED], bump)]
+    pub creator_allow_list: Option<Box<Account<'info, CreatorAllowList>>>,
+
+    #[account(seeds = [NFT_COLLECTION_SEED, nft_collection.collection.as_ref()], bump)]
+    pub nft_collection: Option<Box<Account<'info, NftCollection>>>,
+
+    /// CHECK: master edition of a programmable NFT, checked by Token Metadata
+    pub edition: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token record of `user_nft_account`, checked by Token Metadata
+    #[account(mut)]
+    pub user_token_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token record of `nft_lock_account`, checked by Token Metadata
+    #[account(mut)]
+    pub lock_token_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: rule set of a programmable NFT, checked by Token Metadata
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: program evaluating `authorization_rules`, checked by Token Metadata
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: checked by address
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub token_metadata_program: Program<'info, Metadata>,
 }
 
-pub fn lock_nft(ctx: Context<LockNft>, stake_index: u64, lock_days: u16) -> Result<()> {
+pub fn lock_nft(
+    ctx: Context<LockNft>,
+    stake_id: u64,
+    lock_days: u16,
+    rarity_proof: Option<RarityProof>
+) -> Result<()> {
     let LockNft {
         config,
         metadata,
         user_nft_account,
         nft_lock_account,
         nft_info,
-        stake_info,
+        stake_entry,
         token_program,
         signer,
         mint,
-        ..
+        creator_allow_list,
+        nft_collection,
+        edition,
+        user_token_record,
+        lock_token_record,
+        authorization_rules,
+        authorization_rules_program,
+        sysvar_instructions,
+        associated_token_program,
+        system_program,
+        token_metadata_program,
     } = ctx.accounts;
 
-    require!((stake_index as usize) < stake_info.stakes.len(), ErrorCode::InvalidStakeIndex);
-    require!(
-        metadata.collection.is_some() &&
-            metadata.collection.as_ref().unwrap().key == config.nft_collection,
-        ErrorCode::InvalidCollection
-    );
-
-    let stake_entry = &mut stake_info.stakes[stake_index as usize];
-
-    require!(stake_entry.nft.is_none(), ErrorCode::NftAlreadyLocked);
+    require!(!config.is_nft_locking_paused(), ErrorCode::NftLockingPaused);
+    verify_nft_eligibility(
+        metadata,
+        config,
+        nft_collection.as_deref().map(|nft_collection| &**nft_collection),
+        creator_allow_list.as_deref().map(|allow_list| &**allow_list)
+    )?;
 
-    let apy = get_apy(lock_days, config.nft_days_apy)?;
+    require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
+
+    // Each additional NFT boosts less than the one before it
+    let stack_multiplier_bps = config
+        .nft_stack_multiplier_bps(stake_entry.nfts.len())
+        .ok_or(ErrorCode::NftStackFull)?;
+
+    // Partner collections bring their own schedule, the primary collection uses the
+    // caps the stake was opened with.
+    let (tier_apy, max_reward_lamports, max_apy_duration_days) = match nft_collection {
+        Some(nft_collection) =>
+            (
+                get_apy(lock_days, &nft_collection.nft_days_apy)?,
+                nft_collection.max_nft_reward_lamports,
+                nft_collection.max_nft_apy_duration_days,
+            ),
+        None =>
+            (
+                get_apy(lock_days, &config.nft_days_apy)?,
+                stake_entry.max_nft_reward_lamports,
+                stake_entry.max_nft_apy_duration_days,
+            ),
+    };
+
+    let rarity_multiplier_bps = resolve_rarity_multiplier(
+        &config.rarity_root,
+        &mint.key(),
+        rarity_proof.as_ref()
+    )?;
+    let apy = scale_apy(scale_apy(tier_apy, rarity_multiplier_bps)?, stack_multiplier_bps)?;
+    let apy = cap_stacked_nft_apy(apy, stake_entry, config.max_stacked_nft_apy)?;
+    let max_reward_lamports = scale_nft_reward_cap(max_reward_lamports, stack_multiplier_bps)?;
 
     require!(
-        nft_info.can_lock(lock_days, stake_entry.max_nft_apy_duration_days),
+        nft_info.can_lock(lock_days, max_apy_duration_days),
         ErrorCode::ExceedsMaxLockDuration
     );
+    nft_info.version = NFT_INFO_VERSION;
 
     let lock_time = Clock::get()?.unix_timestamp;
 
-    stake_entry.add_nft_info(mint.key(), lock_time, lock_days, apy);
-
-    transfer_tokens(
-        user_nft_account.to_account_info(),
-        nft_lock_account.to_account_info(),
-        signer.to_account_info(),
-        1,
-        token_program.to_account_info(),
-        None
-    )?;
+    release_reward_liability(config, stake_entry, lock_time)?;
+    stake_entry.add_nft(LockedNft {
+        nft: mint.key(),
+        lock_time,
+        lock_days,
+        apy,
+        unlock_time: None,
+        max_reward_lamports,
+        max_apy_duration_days,
+    })?;
+    commit_reward_liability(config, stake_entry, lock_time)?;
+
+    if is_programmable_nft(metadata) {
+        transfer_programmable_nft(
+            token_metadata_program.to_account_info(),
+            ProgrammableNftTransfer {
+                token: user_nft_account.to_account_info(),
+                token_owner: signer.to_account_info(),
+                destination_token: nft_lock_account.to_account_info(),
+                destination_owner: nft_lock_account.to_account_info(),
+                mint: mint.to_account_info(),
+                metadata: metadata.to_account_info(),
+                edition: programmable_nft_account(edition.as_ref())?,
+                token_record: programmable_nft_account(user_token_record.as_ref())?,
+                destination_token_record: programmable_nft_account(lock_token_record.as_ref())?,
+                authority: signer.to_account_info(),
+                payer: signer.to_account_info(),
+                system_program: system_program.to_account_info(),
+                sysvar_instructions: programmable_nft_account(sysvar_instructions.as_ref())?,
+                token_program: token_program.to_account_info(),
+                associated_token_program: associated_token_program.to_account_info(),
+                authorization_rules_program: authorization_rules_program
+                    .as_ref()
+                    .map(|program| program.to_account_info()),
+                authorization_rules: authorization_rules
+                    .as_ref()
+                    .map(|rules| rules.to_account_info()),
+            },
+            None
+        )?;
+    } else {
+        transfer_tokens(
+            user_nft_account.to_account_info(),
+            nft_lock_account.to_account_info(),
+            signer.to_account_info(),
+            1,
+            token_program.to_account_info(),
+            None
+        )?;
+    }
+
+    emit!(NftLocked {
+        owner: signer.key(),
+        stake_id,
+        nft: mint.key(),
+        lock_time,
+        lock_days,
+        apy,
+        rarity_multiplier_bps,
+        stack_multiplier_bps,
+    });
 
     Ok(())
 }
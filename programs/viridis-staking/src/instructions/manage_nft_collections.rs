@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::events::{ NftCollectionRemoved, NftCollectionUpdated };
+use crate::utils::validate_nft_schedule;
+
+#[derive(Accounts)]
+pub struct AddNftCollection<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.config_change_delay == 0 @ ErrorCode::ConfigChangeTimelocked
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = config_manager,
+        space = 8 + NftCollection::INIT_SPACE,
+        seeds = [NFT_COLLECTION_SEED, collection.key().as_ref()],
+        bump
+    )]
+    pub nft_collection: Account<'info, NftCollection>,
+
+    /// CHECK: Collection mint that NFTs must be verified against
+    pub collection: AccountInfo<'info>,
+
+    #[account(mut, address = config.config_manager @ ErrorCode::Unauthorized)]
+    pub config_manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateNftCollection<'info> {
+    // Schedules are frozen while config changes are timelocked, until a proposal lifts the delay
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.config_change_delay == 0 @ ErrorCode::ConfigChangeTimelocked
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [NFT_COLLECTION_SEED, nft_collection.collection.as_ref()],
+        bump
+    )]
+    pub nft_collection: Account<'info, NftCollection>,
+
+    #[account(address = config.config_manager @ ErrorCode::Unauthorized)]
+    pub config_manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveNftCollection<'info> {
+    // Removing and re-adding a collection would otherwise reschedule it past the timelock
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.config_change_delay == 0 @ ErrorCode::ConfigChangeTimelocked
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [NFT_COLLECTION_SEED, nft_collection.collection.as_ref()],
+        bump,
+        close = config_manager
+    )]
+    pub nft_collection: Account<'info, NftCollection>,
+
+    #[account(mut, address = config.config_manager @ ErrorCode::Unauthorized)]
+    pub config_manager: Signer<'info>,
+}
+
+pub fn add_nft_collection(ctx: Context<AddNftCollection>, args: NftCollectionArgs) -> Result<()> {
+    let collection = ctx.accounts.collection.key();
+
+    require!(collection != ctx.accounts.config.nft_collection, ErrorCode::PrimaryNftCollection);
+
+    let nft_collection = &mut ctx.accounts.nft_collection;
+    nft_collection.version = NFT_COLLECTION_VERSION;
+    nft_collection.collection = collection;
+
+    set_nft_collection_schedule(nft_collection, args, &ctx.accounts.config_manager)
+}
+
+pub fn update_nft_collection(
+    ctx: Context<UpdateNftCollection>,
+    args: NftCollectionArgs
+) -> Result<()> {
+    set_nft_collection_schedule(
+        &mut ctx.accounts.nft_collection,
+        args,
+        &ctx.accounts.config_manager
+    )
+}
+
+/// Stakes that already locked an NFT from the collection keep the schedule they locked with,
+/// and `unlock_nft` does not need the collection to return the NFT.
+pub fn remove_nft_collection(ctx: Context<RemoveNftCollection>) -> Result<()> {
+    emit!(NftCollectionRemoved {
+        authority: ctx.accounts.config_manager.key(),
+        collection: ctx.accounts.nft_collection.collection,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+fn set_nft_collection_schedule(
+    nft_collection: &mut NftCollection,
+    args: NftCollectionArgs,
+    config_manager: &Signer
+) -> Result<()> {
+    validate_nft_schedule(&args.nft_days_apy, args.max_nft_apy_duration_days)?;
+
+    nft_collection.max_nft_reward_lamports = args.max_nft_reward_lamports;
+    nft_collection.max_nft_apy_duration_days = args.max_nft_apy_duration_days;
+    nft_collection.nft_days_apy = args.nft_days_apy;
+
+    emit!(NftCollectionUpdated {
+        authority: config_manager.key(),
+        collection: nft_collection.collection,
+        max_nft_reward_lamports: nft_collection.max_nft_reward_lamports,
+        max_nft_apy_duration_days: nft_collection.max_nft_apy_duration_days,
+        nft_days_apy: nft_collection.nft_days_apy.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
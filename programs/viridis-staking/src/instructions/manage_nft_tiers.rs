@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::utils;
+use crate::constants::CONFIG_SEED;
+use crate::events::NftTiersUpdated;
+
+#[derive(Accounts)]
+pub struct ManageNftTiers<'info> {
+    // Once a delay is set, tier changes have to go through `propose_config_change`
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.config_change_delay == 0 @ ErrorCode::ConfigChangeTimelocked
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.config_manager @ ErrorCode::Unauthorized)]
+    pub config_manager: Signer<'info>,
+}
+
+pub fn add_nft_tier(ctx: Context<ManageNftTiers>, days: u16, apy: u16) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    require!(days <= config.max_nft_apy_duration_days, ErrorCode::NftTierExceedsMaxDuration);
+
+    utils::insert_nft_tier(&mut config.nft_days_apy, NftApy { days, apy, enabled: true })?;
+
+    emit_nft_tiers_updated(&ctx.accounts.config_manager, config)
+}
+
+pub fn remove_nft_tier(ctx: Context<ManageNftTiers>, days: u16) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    utils::remove_nft_tier(&mut config.nft_days_apy, days)?;
+
+    emit_nft_tiers_updated(&ctx.accounts.config_manager, config)
+}
+
+pub fn set_nft_tier_enabled(ctx: Context<ManageNftTiers>, days: u16, enabled: bool) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    utils::set_nft_tier_enabled(&mut config.nft_days_apy, days, enabled)?;
+
+    emit_nft_tiers_updated(&ctx.accounts.config_manager, config)
+}
+
+fn emit_nft_tiers_updated(config_manager: &Signer, config: &Config) -> Result<()> {
+    emit!(NftTiersUpdated {
+        authority: config_manager.key(),
+        nft_days_apy: config.nft_days_apy.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{ Mint, TokenAccount };
+
+use crate::constants::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::events::{ AccountMigrated, StakeInfoShrunk };
+use crate::legacy::*;
+use crate::utils::{ count_legacy_stakes, write_account };
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: read with the legacy layout and rewritten in `migrate_config`
+    #[account(mut, seeds = [CONFIG_SEED], bump, owner = crate::ID)]
+    pub config: UncheckedAccount<'info>,
+
+    #[account(seeds = [VAULT_SEED], bump)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    // The vault was created for the single mint the program staked and paid rewards in
+    #[account(address = token_vault.mint @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStakeInfo<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: read with the legacy layout and rewritten in `migrate_stake_info`
+    #[account(
+        mut,
+        seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub stake_info: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateNftInfo<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECK: read with the legacy layout and rewritten in `migrate_nft_info`
+    #[account(
+        mut,
+        seeds = [NFT_INFO_SEED, mint.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub nft_info: UncheckedAccount<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// `legacy_stake_infos` is the number of baseline stake infos, which keep the vault from being
+/// withdrawn from until they are all migrated.
+pub fn migrate_config(ctx: Context<MigrateConfig>, legacy_stake_infos: u32) -> Result<()> {
+    let MigrateConfig { admin, config, mint, system_program, .. } = ctx.accounts;
+
+    let legacy: ConfigV0 = {
+        let data = config.try_borrow_data()?;
+        read_legacy_account::<Config, _>(&data, data.len() == 8 + ConfigV0::INIT_SPACE)?
+    };
+    require!(legacy.admin == admin.key(), ErrorCode::Unauthorized);
+
+    write_account(
+        config,
+        admin,
+        system_program,
+        &legacy.into_config(mint.key(), mint.decimals, legacy_stake_infos),
+        8 + Config::INIT_SPACE
+    )?;
+
+    emit_account_migrated(config.key(), CONFIG_VERSION)
+}
+
+pub fn migrate_stake_info(ctx: Context<MigrateStakeInfo>) -> Result<()> {
+    let MigrateStakeInfo { signer, config, stake_info, system_program } = ctx.accounts;
+
+    let legacy: StakeInfoV0 = {
+        let data = stake_info.try_borrow_data()?;
+        let is_legacy = data.get(8..40) == Some(signer.key.as_ref());
+        read_legacy_account::<StakeInfo, _>(&data, is_legacy)?
+    };
+
+    // Baseline stake infos were over-allocated, so the account is sized to the migrated entries
+    // and the surplus rent goes back to the owner
+    let mut migrated = StakeInfo::from(legacy);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    count_legacy_stakes(config, &mut migrated, current_time)?;
+
+    let space = 8 + migrated.try_to_vec()?.len();
+
+    let refunded_lamports = write_account(stake_info, signer, system_program, &migrated, space)?;
+
+    emit!(StakeInfoShrunk {
+        owner: signer.key(),
+        refunded_lamports,
+        timestamp: current_time,
+    });
+
+    emit_account_migrated(stake_info.key(), STAKE_INFO_VERSION)
+}
+
+pub fn migrate_nft_info(ctx: Context<MigrateNftInfo>) -> Result<()> {
+    let MigrateNftInfo { signer, nft_info, system_program, .. } = ctx.accounts;
+
+    let legacy: NftInfoV0 = {
+        let data = nft_info.try_borrow_data()?;
+        read_legacy_account::<NftInfo, _>(&data, data.len() == 8 + NftInfoV0::INIT_SPACE)?
+    };
+
+    write_account(
+        nft_info,
+        signer,
+        system_program,
+        &NftInfo::from(legacy),
+        8 + NftInfo::INIT_SPACE
+    )?;
+
+    emit_account_migrated(nft_info.key(), NFT_INFO_VERSION)
+}
+
+fn emit_account_migrated(account: Pubkey, version: u8) -> Result<()> {
+    emit!(AccountMigrated {
+        account,
+        version,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::events::StakeEntryMigrated;
+use crate::utils::shrink_account;
+
+#[derive(Accounts)]
+#[instruction(stake_id: u64)]
+pub struct MigrateStakeEntry<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
+        bump,
+    )]
+    pub stake_info: Account<'info, StakeInfo>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + StakeEntry::INIT_SPACE,
+        seeds = [STAKE_ENTRY_SEED, signer.key.as_ref(), &stake_id.to_le_bytes()],
+        bump
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Moves an entry out of the legacy `StakeInfo.stakes` list into its own account, keeping
+/// its id, and refunds the rent freed in the stake info.
+pub fn migrate_stake_entry(ctx: Context<MigrateStakeEntry>, stake_id: u64) -> Result<()> {
+    let MigrateStakeEntry { signer, stake_info, stake_entry, .. } = ctx.accounts;
+
+    let position = stake_info.find_stake_position(stake_id)?;
+    stake_entry.set_inner(stake_info.stakes.remove(position));
+
+    let refunded_lamports = shrink_account(stake_info, signer)?;
+
+    emit!(StakeEntryMigrated {
+        owner: signer.key(),
+        stake_id,
+        refunded_lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -24,3 +24,63 @@ pub use unlock_nft::*;
 
 mod destake;
 pub use destake::*;
+
+mod partial_destake;
+pub use partial_destake::*;
+
+mod fund_vault;
+pub use fund_vault::*;
+
+mod withdraw_vault;
+pub use withdraw_vault::*;
+
+mod vault_health;
+pub use vault_health::*;
+
+mod set_pause_state;
+pub use set_pause_state::*;
+
+mod emergency_withdraw;
+pub use emergency_withdraw::*;
+
+mod manage_nft_tiers;
+pub use manage_nft_tiers::*;
+
+mod compound;
+pub use compound::*;
+
+mod increase_stake;
+pub use increase_stake::*;
+
+mod early_destake;
+pub use early_destake::*;
+
+mod close_stake_entry;
+pub use close_stake_entry::*;
+
+mod migrate_stake_entry;
+pub use migrate_stake_entry::*;
+
+mod shrink_stake_info;
+pub use shrink_stake_info::*;
+
+mod migrate_accounts;
+pub use migrate_accounts::*;
+
+mod transfer_admin;
+pub use transfer_admin::*;
+
+mod set_roles;
+pub use set_roles::*;
+
+mod config_change;
+pub use config_change::*;
+
+mod set_allowed_creators;
+pub use set_allowed_creators::*;
+
+mod manage_nft_collections;
+pub use manage_nft_collections::*;
+
+mod close_stake_info;
+pub use close_stake_info::*;
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+use crate::utils::*;
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::events::PartiallyDestaked;
+
+use super::Destake;
+
+pub fn partial_destake(ctx: Context<Destake>, stake_id: u64, amount: u64) -> Result<()> {
+    let Destake {
+        token_program,
+        config,
+        stake_entry,
+        token_vault,
+        user_token,
+        user_reward_token,
+        stake_account,
+        ..
+    } = ctx.accounts;
+
+    require!(!config.is_claims_paused(), ErrorCode::ClaimsPaused);
+    require!(amount > 0, ErrorCode::InvalidPartialAmount);
+    require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    check_lock_periods_ended(stake_entry, current_time)?;
+
+    release_reward_liability(config, stake_entry, current_time)?;
+
+    let claimable_reward = calculate_claimable_reward(stake_entry, current_time)?;
+
+    if claimable_reward > 0 {
+        require!(token_vault.amount >= claimable_reward, ErrorCode::InsufficientVaultBalance);
+
+        stake_entry.add_payment(claimable_reward);
+        config.add_payment(claimable_reward);
+
+        transfer_tokens(
+            token_vault.to_account_info(),
+            user_reward_token.to_account_info(),
+            token_vault.to_account_info(),
+            claimable_reward,
+            token_program.to_account_info(),
+            Some(&[&[VAULT_SEED, &[ctx.bumps.token_vault]]])
+        )?;
+    }
+
+    remove_principal(stake_entry, amount, &config.amount_brackets, current_time)?;
+
+    config.remove_stake(amount);
+    commit_reward_liability(config, stake_entry, current_time)?;
+
+    transfer_tokens(
+        stake_account.to_account_info(),
+        user_token.to_account_info(),
+        stake_account.to_account_info(),
+        amount,
+        token_program.to_account_info(),
+        Some(&[&[TOKEN_SEED, ctx.accounts.signer.key.as_ref(), &[ctx.bumps.stake_account]]])
+    )?;
+
+    emit!(PartiallyDestaked {
+        owner: ctx.accounts.signer.key(),
+        stake_id,
+        amount,
+        remaining_amount: stake_entry.amount,
+        reward: claimable_reward,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
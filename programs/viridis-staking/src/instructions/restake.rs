@@ -1,11 +1,16 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::clock::SECONDS_PER_DAY;
 use anchor_spl::token::{ Mint, Token, TokenAccount };
-use crate::utils::{ calculate_claimable_reward, resize_account, transfer_tokens };
-use crate::{ constants::*, error::ErrorCode, state::* };
+use crate::utils::{
+    calculate_claimable_reward,
+    commit_reward_liability,
+    release_reward_liability,
+    transfer_tokens,
+};
+use crate::{ constants::*, error::ErrorCode, events::Restaked, state::* };
 
 #[derive(Accounts)]
-#[instruction(amount: u64)]
+#[instruction(stake_id: u64)]
 pub struct Restake<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
@@ -20,8 +25,12 @@ pub struct Restake<'info> {
     #[account(mut, seeds = [VAULT_SEED], bump)]
     pub token_vault: Account<'info, TokenAccount>,
 
-    #[account(mut, associated_token::mint = mint, associated_token::authority = signer)]
-    pub user_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = signer
+    )]
+    pub user_reward_token: Account<'info, TokenAccount>,
 
     #[account(
         mut,
@@ -30,61 +39,108 @@ pub struct Restake<'info> {
     )]
     pub stake_info: Account<'info, StakeInfo>,
 
-    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [STAKE_ENTRY_SEED, signer.key().as_ref(), &stake_id.to_le_bytes()],
+        bump,
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + StakeEntry::INIT_SPACE,
+        seeds = [
+            STAKE_ENTRY_SEED,
+            signer.key().as_ref(),
+            &stake_info.next_stake_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub new_stake_entry: Account<'info, StakeEntry>,
+
+    #[account(address = config.reward_mint @ ErrorCode::InvalidMint)]
+    pub reward_mint: Account<'info, Mint>,
 
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn restake(ctx: Context<Restake>, stake_index: u64) -> Result<()> {
-    let Restake { config, stake_info, token_vault, token_program, user_token, .. } = ctx.accounts;
-
-    require!((stake_index as usize) < stake_info.stakes.len(), ErrorCode::InvalidStakeIndex);
-    let stake_entry = &mut stake_info.stakes[stake_index as usize];
-    require!(stake_entry.parent_stake_index.is_none(), ErrorCode::AlreadyRestaked);
+pub fn restake(ctx: Context<Restake>, stake_id: u64) -> Result<()> {
+    let Restake {
+        config,
+        stake_info,
+        stake_entry,
+        new_stake_entry,
+        token_vault,
+        token_program,
+        user_reward_token,
+        ..
+    } = ctx.accounts;
+
+    require!(!config.is_staking_paused(), ErrorCode::StakingPaused);
+    require!(!config.is_claims_paused(), ErrorCode::ClaimsPaused);
+
+    require!(stake_entry.parent_stake_id.is_none(), ErrorCode::AlreadyRestaked);
     require!(stake_entry.destake_time.is_none(), ErrorCode::AlreadyDestaked);
 
-    let nft_lock_days = stake_entry.nft_lock_days.ok_or(ErrorCode::NoNftLocked)?;
-    let nft_lock_time = stake_entry.nft_lock_time.ok_or(ErrorCode::NoNftLocked)?;
-    let nft = stake_entry.nft.ok_or(ErrorCode::NoNftLocked)?;
-    let nft_apy = stake_entry.nft_apy.ok_or(ErrorCode::NoNftLocked)?;
+    require!(stake_entry.is_nft_locked(), ErrorCode::NoNftLocked);
 
     let current_time = Clock::get()?.unix_timestamp;
+    release_reward_liability(config, stake_entry, current_time)?;
+
+    // Every locked NFT has to reach 1/3 of its lock period before the stake can be restaked
+    let mut restake_time = current_time;
+    for locked_nft in stake_entry.locked_nfts() {
+        let one_third_lock_period_days = (locked_nft.lock_days as i64) / 3;
 
-    let one_third_lock_period_days = (nft_lock_days as i64) / 3;
+        let one_third_lock_period_seconds = one_third_lock_period_days * (SECONDS_PER_DAY as i64);
 
-    let one_third_lock_period_seconds = one_third_lock_period_days * (SECONDS_PER_DAY as i64);
+        let min_restake_start_time = locked_nft.lock_time
+            .checked_add(one_third_lock_period_seconds)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-    let min_restake_start_time = nft_lock_time
-        .checked_add(one_third_lock_period_seconds)
-        .ok_or(ErrorCode::ArithmeticOverflow)?;
+        restake_time = restake_time.max(min_restake_start_time);
+    }
 
-    let restake_time = min_restake_start_time.max(current_time);
+    let locked_nfts: Vec<LockedNft> = stake_entry.locked_nfts().copied().collect();
 
     stake_entry.restake_time = Some(restake_time);
     stake_entry.destake_time = Some(restake_time);
-    stake_entry.nft_unlock_time = Some(restake_time);
+    for locked_nft in stake_entry.nfts.iter_mut().filter(|nft| nft.unlock_time.is_none()) {
+        locked_nft.unlock_time = Some(restake_time);
+    }
 
+    // The new stake keeps the lock its base APY was priced for
     let new_stake = &mut StakeEntry::new(
         stake_entry.amount,
         restake_time,
-        config.base_lock_days,
+        stake_entry.stake_lock_days,
         stake_entry.base_apy,
         stake_entry.max_nft_reward_lamports,
         stake_entry.max_nft_apy_duration_days,
-        Some(stake_index)
+        stake_entry.reward_rate,
+        Some(stake_id)
     );
+    new_stake.amount_bonus_apy = stake_entry.amount_bonus_apy;
 
-    new_stake.add_nft_info(nft, restake_time, nft_lock_days, nft_apy);
+    for locked_nft in locked_nfts {
+        new_stake.add_nft(LockedNft { lock_time: restake_time, ..locked_nft })?;
+    }
+
+    commit_reward_liability(config, new_stake, current_time)?;
 
     let claimable_reward = calculate_claimable_reward(stake_entry, restake_time)?;
 
     if claimable_reward > 0 {
+        require!(token_vault.amount >= claimable_reward, ErrorCode::InsufficientVaultBalance);
+
         stake_entry.add_payment(claimable_reward);
+        config.add_payment(claimable_reward);
 
         transfer_tokens(
             token_vault.to_account_info(),
-            user_token.to_account_info(),
+            user_reward_token.to_account_info(),
             token_vault.to_account_info(),
             claimable_reward,
             token_program.to_account_info(),
@@ -92,13 +148,18 @@ pub fn restake(ctx: Context<Restake>, stake_index: u64) -> Result<()> {
         )?;
     }
 
-    resize_account(
-        stake_info,
-        &ctx.accounts.signer,
-        &ctx.accounts.system_program,
-        std::mem::size_of::<StakeEntry>()
-    )?;
-    stake_info.stakes.push(new_stake.clone());
+    let new_stake_id = stake_info.open_stake()?;
+    new_stake.id = new_stake_id;
+    new_stake_entry.set_inner(new_stake.clone());
+
+    emit!(Restaked {
+        owner: ctx.accounts.signer.key(),
+        stake_id,
+        new_stake_id,
+        amount: new_stake.amount,
+        reward: claimable_reward,
+        restake_time,
+    });
 
     Ok(())
 }
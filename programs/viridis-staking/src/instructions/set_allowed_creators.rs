@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::events::AllowedCreatorsUpdated;
+
+#[derive(Accounts)]
+pub struct SetAllowedCreators<'info> {
+    // The allow-list decides which NFTs earn a boost, so it is timelocked like the tiers
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+        constraint = config.config_change_delay == 0 @ ErrorCode::ConfigChangeTimelocked
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = config_manager,
+        space = 8 + CreatorAllowList::INIT_SPACE,
+        seeds = [CREATOR_ALLOW_LIST_SEED],
+        bump
+    )]
+    pub creator_allow_list: Account<'info, CreatorAllowList>,
+
+    #[account(mut, address = config.config_manager @ ErrorCode::Unauthorized)]
+    pub config_manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_allowed_creators(ctx: Context<SetAllowedCreators>, creators: Vec<Pubkey>) -> Result<()> {
+    require!(creators.len() <= MAX_ALLOWED_CREATORS, ErrorCode::TooManyAllowedCreators);
+
+    let creator_allow_list = &mut ctx.accounts.creator_allow_list;
+
+    creator_allow_list.version = CREATOR_ALLOW_LIST_VERSION;
+    creator_allow_list.creators = creators;
+
+    emit!(AllowedCreatorsUpdated {
+        authority: ctx.accounts.config_manager.key(),
+        creators: creator_allow_list.creators.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::constants::CONFIG_SEED;
+use crate::events::PauseStateUpdated;
+
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct SetPauseStateArgs {
+    pub staking_paused: Option<bool>,
+    pub nft_locking_paused: Option<bool>,
+    pub claims_paused: Option<bool>,
+    pub emergency_mode: Option<bool>,
+}
+
+#[derive(Accounts)]
+pub struct SetPauseState<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.pauser @ ErrorCode::Unauthorized)]
+    pub pauser: Signer<'info>,
+}
+
+pub fn set_pause_state(ctx: Context<SetPauseState>, args: SetPauseStateArgs) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    if let Some(staking_paused) = args.staking_paused {
+        config.staking_paused = staking_paused;
+    }
+
+    if let Some(nft_locking_paused) = args.nft_locking_paused {
+        config.nft_locking_paused = nft_locking_paused;
+    }
+
+    if let Some(claims_paused) = args.claims_paused {
+        config.claims_paused = claims_paused;
+    }
+
+    if let Some(emergency_mode) = args.emergency_mode {
+        config.emergency_mode = emergency_mode;
+    }
+
+    emit!(PauseStateUpdated {
+        authority: ctx.accounts.pauser.key(),
+        staking_paused: config.staking_paused,
+        nft_locking_paused: config.nft_locking_paused,
+        claims_paused: config.claims_paused,
+        emergency_mode: config.emergency_mode,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::CONFIG_SEED;
+use crate::error::ErrorCode;
+use crate::events::RolesUpdated;
+
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct SetRolesArgs {
+    pub config_manager: Option<Pubkey>,
+    pub treasury_manager: Option<Pubkey>,
+    pub pauser: Option<Pubkey>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoles<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.admin @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+}
+
+pub fn set_roles(ctx: Context<SetRoles>, args: SetRolesArgs) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    if let Some(config_manager) = args.config_manager {
+        config.config_manager = config_manager;
+    }
+
+    if let Some(treasury_manager) = args.treasury_manager {
+        config.treasury_manager = treasury_manager;
+    }
+
+    if let Some(pauser) = args.pauser {
+        config.pauser = pauser;
+    }
+
+    emit!(RolesUpdated {
+        admin: ctx.accounts.admin.key(),
+        config_manager: config.config_manager,
+        treasury_manager: config.treasury_manager,
+        pauser: config.pauser,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::*;
+use crate::events::StakeInfoShrunk;
+use crate::legacy::*;
+use crate::utils::truncate_account;
+
+#[derive(Accounts)]
+pub struct ShrinkStakeInfo<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECK: read with the legacy layout and truncated in `shrink_stake_info`
+    #[account(
+        mut,
+        seeds = [STAKE_INFO_SEED, signer.key.as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub stake_info: UncheckedAccount<'info>,
+}
+
+/// Truncates a baseline stake info to the serialized size of its entries and refunds the
+/// rent of the space `stake` and `restake` over-allocated. The account keeps its legacy layout
+/// and can still be migrated later.
+pub fn shrink_stake_info(ctx: Context<ShrinkStakeInfo>) -> Result<()> {
+    let ShrinkStakeInfo { signer, stake_info } = ctx.accounts;
+
+    let space = {
+        let data = stake_info.try_borrow_data()?;
+        let is_legacy = data.get(8..40) == Some(signer.key.as_ref());
+        let legacy: StakeInfoV0 = read_legacy_account::<StakeInfo, _>(&data, is_legacy)?;
+        8 + legacy.try_to_vec()?.len()
+    };
+
+    let refunded_lamports = truncate_account(stake_info, signer, space)?;
+
+    emit!(StakeInfoShrunk {
+        owner: signer.key(),
+        refunded_lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{ associated_token::AssociatedToken, token::{ Mint, Token, TokenAccount } };
-use crate::utils::{ resize_account, transfer_tokens };
-use crate::{ constants::*, error::ErrorCode, state::* };
+use crate::utils::{
+    commit_reward_liability,
+    get_amount_bonus_apy,
+    get_base_apy,
+    transfer_tokens,
+};
+use crate::{ constants::*, error::ErrorCode, events::StakeCreated, state::* };
 
 #[derive(Accounts)]
 #[instruction(amount: u64)]
@@ -23,6 +28,19 @@ pub struct Stake<'info> {
     )]
     pub stake_info: Account<'info, StakeInfo>,
 
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + StakeEntry::INIT_SPACE,
+        seeds = [
+            STAKE_ENTRY_SEED,
+            signer.key().as_ref(),
+            &stake_info.next_stake_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub stake_entry: Account<'info, StakeEntry>,
+
     #[account(
         init_if_needed,
         seeds = [TOKEN_SEED, signer.key().as_ref()],
@@ -40,6 +58,7 @@ pub struct Stake<'info> {
     )]
     pub user_token_account: Account<'info, TokenAccount>,
 
+    #[account(address = config.mint @ ErrorCode::InvalidMint)]
     pub mint: Account<'info, Mint>,
 
     pub token_program: Program<'info, Token>,
@@ -47,29 +66,42 @@ pub struct Stake<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+pub fn stake(ctx: Context<Stake>, amount: u64, lock_days: Option<u16>) -> Result<()> {
     require!(amount > 0, ErrorCode::NoTokens);
+    require!(!ctx.accounts.config.is_staking_paused(), ErrorCode::StakingPaused);
 
     let stake_info = &mut ctx.accounts.stake_info;
     let config = &mut ctx.accounts.config;
     let start_time = Clock::get()?.unix_timestamp;
-    let new_stake = StakeEntry::new(
-        amount,
-        start_time,
+
+    let stake_lock_days = lock_days.unwrap_or(config.base_lock_days);
+    let lock_apy = get_base_apy(
+        stake_lock_days,
         config.base_lock_days,
         config.base_apy,
+        &config.base_apy_tiers
+    )?;
+    let amount_bonus_apy = get_amount_bonus_apy(amount, &config.amount_brackets);
+    let base_apy = lock_apy.checked_add(amount_bonus_apy).ok_or(ErrorCode::MathOverflow)?;
+
+    let mut new_stake = StakeEntry::new(
+        amount,
+        start_time,
+        stake_lock_days,
+        base_apy,
         config.max_nft_reward_lamports,
         config.max_nft_apy_duration_days,
+        config.reward_rate,
         None
     );
+    new_stake.amount_bonus_apy = amount_bonus_apy;
 
-    resize_account(
-        stake_info,
-        &ctx.accounts.signer,
-        &ctx.accounts.system_program,
-        std::mem::size_of::<StakeEntry>()
-    )?;
-    stake_info.stakes.push(new_stake);
+    commit_reward_liability(config, &mut new_stake, start_time)?;
+    config.add_stake(amount)?;
+
+    let stake_id = stake_info.open_stake()?;
+    new_stake.id = stake_id;
+    ctx.accounts.stake_entry.set_inner(new_stake);
 
     transfer_tokens(
         ctx.accounts.user_token_account.to_account_info(),
@@ -80,5 +112,14 @@ pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
         None
     )?;
 
+    emit!(StakeCreated {
+        owner: ctx.accounts.signer.key(),
+        stake_id,
+        amount,
+        start_time,
+        stake_lock_days,
+        base_apy,
+    });
+
     Ok(())
-}
\ No newline at end of file
+}
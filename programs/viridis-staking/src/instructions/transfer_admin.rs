@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::CONFIG_SEED;
+use crate::error::ErrorCode;
+use crate::events::{ AdminTransferProposed, AdminTransferred };
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.admin @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.pending_admin @ ErrorCode::Unauthorized)]
+    pub pending_admin: Signer<'info>,
+}
+
+/// Nominates the next admin, who has to accept before the handover takes effect.
+/// Proposing the default pubkey cancels a pending transfer.
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.pending_admin = new_admin;
+
+    emit!(AdminTransferProposed {
+        admin: ctx.accounts.admin.key(),
+        pending_admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(config.pending_admin != Pubkey::default(), ErrorCode::NoPendingAdmin);
+
+    let old_admin = config.admin;
+    config.admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+
+    emit!(AdminTransferred {
+        old_admin,
+        new_admin: config.admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -1,27 +1,31 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{ prelude::*, solana_program::sysvar };
 use anchor_spl::{
     associated_token::AssociatedToken,
     metadata::{ Metadata, MetadataAccount },
     token::{ Mint, Token, TokenAccount },
 };
-use crate::utils::{ calculate_days_passed, transfer_tokens };
-use crate::{ constants::*, error::ErrorCode, state::* };
+use crate::utils::{
+    calculate_days_passed,
+    is_programmable_nft,
+    programmable_nft_account,
+    transfer_programmable_nft,
+    transfer_tokens,
+    ProgrammableNftTransfer,
+};
+use crate::{ constants::*, error::ErrorCode, events::NftUnlocked, state::* };
 
 #[derive(Accounts)]
-#[instruction(stake_index: u64)]
+#[instruction(stake_id: u64)]
 pub struct UnlockNft<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
-    #[account(seeds = [CONFIG_SEED], bump)]
-    pub config: Account<'info, Config>,
-
     #[account(
         mut,
-        seeds = [STAKE_INFO_SEED, signer.key().as_ref()],
+        seeds = [STAKE_ENTRY_SEED, signer.key().as_ref(), &stake_id.to_le_bytes()],
         bump,
     )]
-    pub stake_info: Account<'info, StakeInfo>,
+    pub stake_entry: Account<'info, StakeEntry>,
 
     #[account(
         mut,
@@ -45,6 +49,7 @@ pub struct UnlockNft<'info> {
     pub user_nft_account: Account<'info, TokenAccount>,
 
     #[account(
+        mut,
         seeds = [METADATA_SEED, token_metadata_program.key().as_ref(), mint.key().as_ref()],
         seeds::program = token_metadata_program.key(),
         bump
@@ -52,55 +57,122 @@ pub struct UnlockNft<'info> {
     pub metadata: Account<'info, MetadataAccount>,
 
     #[account(
-        constraint = stake_info.stakes[stake_index as usize].nft == Some(mint.key()) @ ErrorCode::InvalidNftMint,
+        constraint = mint.supply == 1 && mint.decimals == 0 @ ErrorCode::InvalidNftMint
     )]
     pub mint: Account<'info, Mint>,
 
+    /// CHECK: master edition of a programmable NFT, checked by Token Metadata
+    pub edition: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token record of `user_nft_account`, checked by Token Metadata
+    #[account(mut)]
+    pub user_token_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: token record of `nft_lock_account`, checked by Token Metadata
+    #[account(mut)]
+    pub lock_token_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: rule set of a programmable NFT, checked by Token Metadata
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: program evaluating `authorization_rules`, checked by Token Metadata
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: checked by address
+    #[account(address = sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub token_metadata_program: Program<'info, Metadata>,
 }
 
-pub fn unlock_nft(ctx: Context<UnlockNft>, stake_index: u64) -> Result<()> {
+/// Returns a locked NFT once its stake is destaked. Eligibility is only checked by `lock_nft`,
+/// so an NFT can always be withdrawn after its collection or the allow list changes.
+pub fn unlock_nft(ctx: Context<UnlockNft>, stake_id: u64) -> Result<()> {
     let UnlockNft {
-        config,
         metadata,
         user_nft_account,
         nft_info,
         nft_lock_account,
         token_program,
-        stake_info,
+        stake_entry,
+        edition,
+        user_token_record,
+        lock_token_record,
+        authorization_rules,
+        authorization_rules_program,
+        sysvar_instructions,
+        associated_token_program,
+        system_program,
+        token_metadata_program,
+        signer,
+        mint,
         ..
     } = ctx.accounts;
 
-    require!((stake_index as usize) < stake_info.stakes.len(), ErrorCode::InvalidStakeIndex);
-    require!(
-        metadata.collection.is_some() &&
-            metadata.collection.as_ref().unwrap().key == config.nft_collection,
-        ErrorCode::InvalidCollection
-    );
-
-    let stake_entry = &mut stake_info.stakes[stake_index as usize];
-
-    require!(stake_entry.nft_unlock_time.is_none(), ErrorCode::NoNftLocked);
-    let nft_lock_time = stake_entry.nft_lock_time.ok_or(ErrorCode::NoNftLocked)?;
     let destake_time = stake_entry.destake_time.ok_or(ErrorCode::StakeNotDestaked)?;
+    let locked_nft = stake_entry
+        .find_locked_nft_mut(mint.key())
+        .ok_or(ErrorCode::InvalidNftMint)?;
 
-    let nft_lock_days = calculate_days_passed(nft_lock_time, destake_time);
+    let nft_lock_days = calculate_days_passed(locked_nft.lock_time, destake_time);
     nft_info.add_days(nft_lock_days as u16);
 
     let clock = Clock::get()?;
-    stake_entry.nft_unlock_time = Some(clock.unix_timestamp);
-
-    transfer_tokens(
-        nft_lock_account.to_account_info(),
-        user_nft_account.to_account_info(),
-        nft_lock_account.to_account_info(),
-        1,
-        token_program.to_account_info(),
-        Some(&[&[NFT_SEED, ctx.accounts.mint.key().as_ref(), &[ctx.bumps.nft_lock_account]]])
-    )?;
+    locked_nft.unlock_time = Some(clock.unix_timestamp);
+
+    let mint_key = mint.key();
+    let nft_lock_bump = [ctx.bumps.nft_lock_account];
+    let signer_seeds: &[&[&[u8]]] = &[&[NFT_SEED, mint_key.as_ref(), &nft_lock_bump]];
+
+    if is_programmable_nft(metadata) {
+        transfer_programmable_nft(
+            token_metadata_program.to_account_info(),
+            ProgrammableNftTransfer {
+                token: nft_lock_account.to_account_info(),
+                token_owner: nft_lock_account.to_account_info(),
+                destination_token: user_nft_account.to_account_info(),
+                destination_owner: signer.to_account_info(),
+                mint: mint.to_account_info(),
+                metadata: metadata.to_account_info(),
+                edition: programmable_nft_account(edition.as_ref())?,
+                token_record: programmable_nft_account(lock_token_record.as_ref())?,
+                destination_token_record: programmable_nft_account(user_token_record.as_ref())?,
+                authority: nft_lock_account.to_account_info(),
+                payer: signer.to_account_info(),
+                system_program: system_program.to_account_info(),
+                sysvar_instructions: programmable_nft_account(sysvar_instructions.as_ref())?,
+                token_program: token_program.to_account_info(),
+                associated_token_program: associated_token_program.to_account_info(),
+                authorization_rules_program: authorization_rules_program
+                    .as_ref()
+                    .map(|program| program.to_account_info()),
+                authorization_rules: authorization_rules
+                    .as_ref()
+                    .map(|rules| rules.to_account_info()),
+            },
+            Some(signer_seeds)
+        )?;
+    } else {
+        transfer_tokens(
+            nft_lock_account.to_account_info(),
+            user_nft_account.to_account_info(),
+            nft_lock_account.to_account_info(),
+            1,
+            token_program.to_account_info(),
+            Some(signer_seeds)
+        )?;
+    }
+
+    emit!(NftUnlocked {
+        owner: signer.key(),
+        stake_id,
+        nft: mint.key(),
+        unlock_time: clock.unix_timestamp,
+        days_locked: nft_lock_days as u16,
+    });
 
     Ok(())
 }
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use crate::{ constants::*, state::* };
+
+#[derive(Accounts)]
+pub struct VaultHealthView<'info> {
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(seeds = [VAULT_SEED], bump)]
+    pub token_vault: Account<'info, TokenAccount>,
+}
+
+pub fn vault_health(ctx: Context<VaultHealthView>) -> Result<VaultHealth> {
+    let VaultHealthView { config, token_vault } = ctx.accounts;
+
+    let reward_liability = config.reward_liability_at(Clock::get()?.unix_timestamp)?;
+
+    Ok(VaultHealth {
+        vault_balance: token_vault.amount,
+        reward_liability,
+        total_staked: config.total_staked,
+        total_paid: config.total_paid,
+        legacy_stake_infos: config.legacy_stake_infos,
+        surplus: token_vault.amount.saturating_sub(reward_liability),
+        shortfall: reward_liability.saturating_sub(token_vault.amount),
+    })
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{ Mint, Token, TokenAccount };
+use crate::utils::{ check_vault_withdrawal, transfer_tokens };
+use crate::{ constants::*, error::ErrorCode, events::VaultWithdrawn, state::* };
+
+#[derive(Accounts)]
+pub struct WithdrawVault<'info> {
+    #[account(seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(address = config.treasury_manager @ ErrorCode::Unauthorized)]
+    pub treasury_manager: Signer<'info>,
+
+    #[account(mut, seeds = [VAULT_SEED], bump, token::mint = reward_mint)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = reward_mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    #[account(address = config.reward_mint @ ErrorCode::InvalidMint)]
+    pub reward_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn withdraw_vault(ctx: Context<WithdrawVault>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let WithdrawVault { config, treasury_manager, token_vault, destination, token_program, .. } =
+        ctx.accounts;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    check_vault_withdrawal(config, token_vault.amount, amount, current_time)?;
+
+    transfer_tokens(
+        token_vault.to_account_info(),
+        destination.to_account_info(),
+        token_vault.to_account_info(),
+        amount,
+        token_program.to_account_info(),
+        Some(&[&[VAULT_SEED, &[ctx.bumps.token_vault]]])
+    )?;
+
+    emit!(VaultWithdrawn {
+        authority: treasury_manager.key(),
+        destination: destination.key(),
+        amount,
+        timestamp: current_time,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{
+    constants::*,
+    error::ErrorCode,
+    state::{
+        Config,
+        LockedNft,
+        NftApy,
+        NftInfo,
+        StakeEntry,
+        StakeInfo,
+    },
+};
+
+// Layouts of accounts written by earlier program versions: V0 is the layout before accounts
+// carried a version byte. They are only read by the `migrate_*` instructions, which rewrite
+// them in the current layout.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ConfigV0 {
+    pub admin: Pubkey,
+    pub nft_collection: Pubkey,
+    pub max_nft_reward_lamports: u64,
+    pub max_nft_apy_duration_days: u16,
+    pub base_lock_days: u16,
+    pub base_apy: u16,
+    pub nft_days_apy: [NftApyV0; 3],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct NftApyV0 {
+    pub days: u16,
+    pub apy: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct StakeInfoV0 {
+    pub address: Pubkey,
+    pub stakes: Vec<StakeEntryV0>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct StakeEntryV0 {
+    pub amount: u64,
+    pub start_time: i64,
+    pub stake_lock_days: u16,
+    pub base_apy: u16,
+    pub nft: Option<Pubkey>,
+    pub nft_lock_time: Option<i64>,
+    pub nft_lock_days: Option<u16>,
+    pub nft_apy: Option<u16>,
+    pub nft_unlock_time: Option<i64>,
+    pub destake_time: Option<i64>,
+    pub restake_time: Option<i64>,
+    pub parent_stake_index: Option<u64>,
+    pub paid_amount: u64,
+    pub max_nft_reward_lamports: u64,
+    pub max_nft_apy_duration_days: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct NftInfoV0 {
+    pub days_locked: u16,
+}
+
+impl ConfigV0 {
+    /// The program only supported a single mint, held by the vault, so it is both the stake
+    /// and the reward mint.
+    pub fn into_config(self, mint: Pubkey, mint_decimals: u8, legacy_stake_infos: u32) -> Config {
+        Config {
+            version: CONFIG_VERSION,
+            admin: self.admin,
+            pending_admin: Pubkey::default(),
+            config_manager: self.admin,
+            treasury_manager: self.admin,
+            pauser: self.admin,
+            mint,
+            mint_decimals,
+            reward_mint: mint,
+            reward_mint_decimals: mint_decimals,
+            reward_rate: (10u64).pow(REWARD_RATE_DECIMALS as u32),
+            nft_collection: self.nft_collection,
+            max_nft_reward_lamports: self.max_nft_reward_lamports,
+            max_nft_apy_duration_days: self.max_nft_apy_duration_days,
+            base_lock_days: self.base_lock_days,
+            base_apy: self.base_apy,
+            nft_days_apy: self.nft_days_apy
+                .iter()
+                .map(|tier| NftApy { days: tier.days, apy: tier.apy, enabled: true })
+                .collect(),
+            base_apy_tiers: Vec::new(),
+            amount_brackets: Vec::new(),
+            top_up_restarts_lock: false,
+            early_destake_penalty_bps: 0,
+            penalty_treasury: Pubkey::default(),
+            reward_liability: 0,
+            reward_accrual_per_year: 0,
+            liability_updated_at: 0,
+            legacy_stake_infos,
+            total_staked: 0,
+            total_paid: 0,
+            staking_paused: false,
+            nft_locking_paused: false,
+            claims_paused: false,
+            emergency_mode: false,
+            config_change_delay: 0,
+            rarity_root: [0; 32],
+            nft_stack_multipliers_bps: Vec::new(),
+            max_stacked_nft_apy: 0,
+            reserved: [0; 4],
+        }
+    }
+}
+
+impl From<StakeInfoV0> for StakeInfo {
+    fn from(stake_info: StakeInfoV0) -> Self {
+        // Entries were addressed by their position, which becomes their id
+        let stakes: Vec<StakeEntry> = stake_info.stakes
+            .into_iter()
+            .zip(0..)
+            .map(|(stake_entry, id)| stake_entry.into_stake_entry(id))
+            .collect();
+
+        Self {
+            version: STAKE_INFO_VERSION,
+            address: stake_info.address,
+            next_stake_id: stakes.len() as u64,
+            stake_count: stakes.len() as u64,
+            reserved: [0; 32],
+            stakes,
+        }
+    }
+}
+
+impl StakeEntryV0 {
+    pub fn into_stake_entry(self, id: u64) -> StakeEntry {
+        // A single locked NFT carried the stake's caps
+        let nft = self.nft.and_then(|nft| {
+            Some(LockedNft {
+                nft,
+                lock_time: self.nft_lock_time?,
+                lock_days: self.nft_lock_days?,
+                apy: self.nft_apy?,
+                unlock_time: self.nft_unlock_time,
+                max_reward_lamports: self.max_nft_reward_lamports,
+                max_apy_duration_days: self.max_nft_apy_duration_days,
+            })
+        });
+
+        StakeEntry {
+            version: STAKE_ENTRY_VERSION,
+            id,
+            amount: self.amount,
+            start_time: self.start_time,
+            stake_lock_days: self.stake_lock_days,
+            base_apy: self.base_apy,
+            amount_bonus_apy: 0,
+            nfts: nft.into_iter().collect(),
+            destake_time: self.destake_time,
+            restake_time: self.restake_time,
+            parent_stake_id: self.parent_stake_index,
+            paid_amount: self.paid_amount,
+            max_nft_reward_lamports: self.max_nft_reward_lamports,
+            max_nft_apy_duration_days: self.max_nft_apy_duration_days,
+            // Rewards were paid 1:1 in the stake mint
+            reward_rate: (10u64).pow(REWARD_RATE_DECIMALS as u32),
+            reward_liability: 0,
+            reward_accrual_per_year: 0,
+            liability_updated_at: 0,
+            reserved: [0; 6],
+        }
+    }
+}
+
+impl From<NftInfoV0> for NftInfo {
+    fn from(nft_info: NftInfoV0) -> Self {
+        Self {
+            version: NFT_INFO_VERSION,
+            days_locked: nft_info.days_locked,
+            reserved: [0; 16],
+        }
+    }
+}
+
+/// Reads an unversioned account written with the discriminator of `T`. Callers decide
+/// whether the data is still on the legacy layout, since a version byte cannot be told apart
+/// from the first byte of an unversioned account.
+pub fn read_legacy_account<T: Discriminator, L: AnchorDeserialize>(
+    data: &[u8],
+    is_legacy: bool
+) -> Result<L> {
+    require!(data.len() >= 8 && data[..8] == T::DISCRIMINATOR, ErrorCode::InvalidAccountLayout);
+    require!(is_legacy, ErrorCode::AccountAlreadyMigrated);
+
+    L::deserialize(&mut &data[8..]).map_err(|_| ErrorCode::InvalidAccountLayout.into())
+}
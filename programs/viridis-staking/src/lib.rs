@@ -2,9 +2,12 @@ use anchor_lang::prelude::*;
 
 mod instructions;
 use instructions::*;
+use state::{ NftCollectionArgs, RarityProof, UpdateConfigArgs, VaultHealth };
 
 mod constants;
 mod error;
+mod events;
+mod legacy;
 mod state;
 mod utils;
 
@@ -29,27 +32,168 @@ pub mod viridis_staking {
         instructions::initialize_stake_info(ctx)
     }
 
-    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
-        instructions::stake(ctx, amount)
+    pub fn stake(ctx: Context<Stake>, amount: u64, lock_days: Option<u16>) -> Result<()> {
+        instructions::stake(ctx, amount, lock_days)
     }
 
-    pub fn restake(ctx: Context<Restake>, stake_index: u64) -> Result<()> {
-        instructions::restake(ctx, stake_index)
+    pub fn restake(ctx: Context<Restake>, stake_id: u64) -> Result<()> {
+        instructions::restake(ctx, stake_id)
     }
 
-    pub fn lock_nft(ctx: Context<LockNft>, stake_index: u64, lock_days: u16) -> Result<()> {
-        instructions::lock_nft(ctx, stake_index, lock_days)
+    pub fn lock_nft(
+        ctx: Context<LockNft>,
+        stake_id: u64,
+        lock_days: u16,
+        rarity_proof: Option<RarityProof>
+    ) -> Result<()> {
+        instructions::lock_nft(ctx, stake_id, lock_days, rarity_proof)
     }
 
-    pub fn unlock_nft(ctx: Context<UnlockNft>, stake_index: u64) -> Result<()> {
-        instructions::unlock_nft(ctx, stake_index)
+    pub fn unlock_nft(ctx: Context<UnlockNft>, stake_id: u64) -> Result<()> {
+        instructions::unlock_nft(ctx, stake_id)
     }
 
-    pub fn claim(ctx: Context<Claim>, stake_index: u64) -> Result<()> {
-        instructions::claim(ctx, stake_index)
+    pub fn claim(ctx: Context<Claim>, stake_id: u64) -> Result<()> {
+        instructions::claim(ctx, stake_id)
     }
 
-    pub fn destake(ctx: Context<Destake>, stake_index: u64) -> Result<()> {
-        instructions::destake(ctx, stake_index)
+    pub fn destake(ctx: Context<Destake>, stake_id: u64) -> Result<()> {
+        instructions::destake(ctx, stake_id)
+    }
+
+    pub fn partial_destake(ctx: Context<Destake>, stake_id: u64, amount: u64) -> Result<()> {
+        instructions::partial_destake(ctx, stake_id, amount)
+    }
+
+    pub fn compound(ctx: Context<Compound>, stake_id: u64) -> Result<()> {
+        instructions::compound(ctx, stake_id)
+    }
+
+    pub fn increase_stake(
+        ctx: Context<IncreaseStake>,
+        stake_id: u64,
+        amount: u64
+    ) -> Result<()> {
+        instructions::increase_stake(ctx, stake_id, amount)
+    }
+
+    pub fn early_destake(ctx: Context<EarlyDestake>, stake_id: u64) -> Result<()> {
+        instructions::early_destake(ctx, stake_id)
+    }
+
+    pub fn close_stake_entry(ctx: Context<CloseStakeEntry>, stake_id: u64) -> Result<()> {
+        instructions::close_stake_entry(ctx, stake_id)
+    }
+
+    pub fn migrate_stake_entry(ctx: Context<MigrateStakeEntry>, stake_id: u64) -> Result<()> {
+        instructions::migrate_stake_entry(ctx, stake_id)
+    }
+
+    pub fn shrink_stake_info(ctx: Context<ShrinkStakeInfo>) -> Result<()> {
+        instructions::shrink_stake_info(ctx)
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>, legacy_stake_infos: u32) -> Result<()> {
+        instructions::migrate_config(ctx, legacy_stake_infos)
+    }
+
+    pub fn migrate_stake_info(ctx: Context<MigrateStakeInfo>) -> Result<()> {
+        instructions::migrate_stake_info(ctx)
+    }
+
+    pub fn migrate_nft_info(ctx: Context<MigrateNftInfo>) -> Result<()> {
+        instructions::migrate_nft_info(ctx)
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn set_roles(ctx: Context<SetRoles>, args: SetRolesArgs) -> Result<()> {
+        instructions::set_roles(ctx, args)
+    }
+
+    pub fn propose_config_change(
+        ctx: Context<ProposeConfigChange>,
+        args: UpdateConfigArgs
+    ) -> Result<()> {
+        instructions::propose_config_change(ctx, args)
+    }
+
+    pub fn execute_config_change(ctx: Context<ManagePendingConfigChange>) -> Result<()> {
+        instructions::execute_config_change(ctx)
+    }
+
+    pub fn cancel_config_change(ctx: Context<ManagePendingConfigChange>) -> Result<()> {
+        instructions::cancel_config_change(ctx)
+    }
+
+    pub fn set_allowed_creators(
+        ctx: Context<SetAllowedCreators>,
+        creators: Vec<Pubkey>
+    ) -> Result<()> {
+        instructions::set_allowed_creators(ctx, creators)
+    }
+
+    pub fn add_nft_collection(
+        ctx: Context<AddNftCollection>,
+        args: NftCollectionArgs
+    ) -> Result<()> {
+        instructions::add_nft_collection(ctx, args)
+    }
+
+    pub fn update_nft_collection(
+        ctx: Context<UpdateNftCollection>,
+        args: NftCollectionArgs
+    ) -> Result<()> {
+        instructions::update_nft_collection(ctx, args)
+    }
+
+    pub fn remove_nft_collection(ctx: Context<RemoveNftCollection>) -> Result<()> {
+        instructions::remove_nft_collection(ctx)
+    }
+
+    pub fn close_stake_info(ctx: Context<CloseStakeInfo>) -> Result<()> {
+        instructions::close_stake_info(ctx)
+    }
+
+    pub fn fund_vault(ctx: Context<FundVault>, amount: u64) -> Result<()> {
+        instructions::fund_vault(ctx, amount)
+    }
+
+    pub fn withdraw_vault(ctx: Context<WithdrawVault>, amount: u64) -> Result<()> {
+        instructions::withdraw_vault(ctx, amount)
+    }
+
+    pub fn vault_health(ctx: Context<VaultHealthView>) -> Result<VaultHealth> {
+        instructions::vault_health(ctx)
+    }
+
+    pub fn set_pause_state(ctx: Context<SetPauseState>, args: SetPauseStateArgs) -> Result<()> {
+        instructions::set_pause_state(ctx, args)
+    }
+
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>, stake_id: u64) -> Result<()> {
+        instructions::emergency_withdraw(ctx, stake_id)
+    }
+
+    pub fn add_nft_tier(ctx: Context<ManageNftTiers>, days: u16, apy: u16) -> Result<()> {
+        instructions::add_nft_tier(ctx, days, apy)
+    }
+
+    pub fn remove_nft_tier(ctx: Context<ManageNftTiers>, days: u16) -> Result<()> {
+        instructions::remove_nft_tier(ctx, days)
+    }
+
+    pub fn set_nft_tier_enabled(
+        ctx: Context<ManageNftTiers>,
+        days: u16,
+        enabled: bool
+    ) -> Result<()> {
+        instructions::set_nft_tier_enabled(ctx, days, enabled)
     }
 }
@@ -1,54 +1,378 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::SECONDS_PER_DAY;
+
+use crate::{
+    constants::{
+        DAYS_PER_YEAR,
+        MAX_ALLOWED_CREATORS,
+        MAX_AMOUNT_BRACKETS,
+        MAX_BASE_APY_TIERS,
+        MAX_BPS,
+        MAX_NFTS_PER_STAKE,
+        MAX_NFT_TIERS,
+        STAKE_ENTRY_VERSION,
+    },
+    error::ErrorCode,
+};
 
 #[account]
+#[derive(InitSpace)]
 pub struct Config {
+    pub version: u8,
     pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub config_manager: Pubkey,
+    pub treasury_manager: Pubkey,
+    pub pauser: Pubkey,
+    pub mint: Pubkey,
+    pub mint_decimals: u8,
+    pub reward_mint: Pubkey,
+    pub reward_mint_decimals: u8,
+    pub reward_rate: u64,
     pub nft_collection: Pubkey,
     pub max_nft_reward_lamports: u64,
     pub max_nft_apy_duration_days: u16,
     pub base_lock_days: u16,
     pub base_apy: u16,
-    pub nft_days_apy: [NftApy; 3],
+    #[max_len(MAX_NFT_TIERS)]
+    pub nft_days_apy: Vec<NftApy>,
+    #[max_len(MAX_BASE_APY_TIERS)]
+    pub base_apy_tiers: Vec<BaseApyTier>,
+    #[max_len(MAX_AMOUNT_BRACKETS)]
+    pub amount_brackets: Vec<AmountBracket>,
+    pub top_up_restarts_lock: bool,
+    pub early_destake_penalty_bps: u16,
+    pub penalty_treasury: Pubkey,
+    // Rewards owed to the active stakes as of `liability_updated_at`, on top of which their base
+    // rewards keep accruing at `reward_accrual_per_year`
+    pub reward_liability: u64,
+    pub reward_accrual_per_year: u64,
+    pub liability_updated_at: i64,
+    // Baseline stake infos whose stakes are not in the liability until they are migrated
+    pub legacy_stake_infos: u32,
+    pub total_staked: u64,
+    pub total_paid: u64,
+    pub staking_paused: bool,
+    pub nft_locking_paused: bool,
+    pub claims_paused: bool,
+    pub emergency_mode: bool,
+    pub config_change_delay: i64,
+    pub rarity_root: [u8; 32],
+    // Multiplier applied to the n-th NFT locked in a stake, empty allows a single NFT at 1x
+    #[max_len(MAX_NFTS_PER_STAKE)]
+    pub nft_stack_multipliers_bps: Vec<u16>,
+    // Cap on the combined APY of the NFTs locked in a stake, zero disables it
+    pub max_stacked_nft_apy: u16,
+    pub reserved: [u8; 4],
 }
 
 impl Config {
-    pub fn len() -> usize {
-        8 + 32 + 32 + 8 + 2 + 2 + 2 + 3 * 4
+    pub fn is_staking_paused(&self) -> bool {
+        self.staking_paused || self.emergency_mode
+    }
+
+    pub fn is_nft_locking_paused(&self) -> bool {
+        self.nft_locking_paused || self.emergency_mode
+    }
+
+    pub fn is_claims_paused(&self) -> bool {
+        self.claims_paused || self.emergency_mode
+    }
+
+    pub fn nft_stack_multiplier_bps(&self, position: usize) -> Option<u16> {
+        if self.nft_stack_multipliers_bps.is_empty() {
+            return (position == 0).then_some(MAX_BPS);
+        }
+
+        self.nft_stack_multipliers_bps.get(position).copied()
+    }
+
+    pub fn penalty_destination(&self, token_vault: Pubkey) -> Pubkey {
+        if self.penalty_treasury == Pubkey::default() { token_vault } else { self.penalty_treasury }
+    }
+
+    pub fn add_stake(&mut self, amount: u64) -> Result<()> {
+        self.total_staked = self.total_staked
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn remove_stake(&mut self, amount: u64) {
+        self.total_staked = self.total_staked.saturating_sub(amount);
+    }
+
+    pub fn add_payment(&mut self, payment: u64) {
+        self.total_paid = self.total_paid.saturating_add(payment);
+    }
+
+    pub fn reward_liability_at(&self, current_time: i64) -> Result<u64> {
+        accrue_liability(
+            self.reward_liability,
+            self.reward_accrual_per_year,
+            self.liability_updated_at,
+            current_time
+        )
+    }
+
+    pub fn release_reward_liability(
+        &mut self,
+        liability: u64,
+        accrual_per_year: u64,
+        current_time: i64
+    ) -> Result<()> {
+        self.reward_liability = self.reward_liability_at(current_time)?.saturating_sub(liability);
+        self.reward_accrual_per_year =
+            self.reward_accrual_per_year.saturating_sub(accrual_per_year);
+        self.liability_updated_at = current_time;
+
+        Ok(())
+    }
+
+    pub fn commit_reward_liability(
+        &mut self,
+        liability: u64,
+        accrual_per_year: u64,
+        current_time: i64
+    ) -> Result<()> {
+        self.reward_liability = self
+            .reward_liability_at(current_time)?
+            .checked_add(liability)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.reward_accrual_per_year = self.reward_accrual_per_year
+            .checked_add(accrual_per_year)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.liability_updated_at = current_time;
+
+        Ok(())
+    }
+
+    pub fn apply_update(&mut self, args: UpdateConfigArgs) {
+        if let Some(base_lock_days) = args.base_lock_days {
+            self.base_lock_days = base_lock_days;
+        }
+
+        if let Some(base_apy) = args.base_apy {
+            self.base_apy = base_apy;
+        }
+
+        if let Some(max_nft_reward_lamports) = args.max_nft_reward_lamports {
+            self.max_nft_reward_lamports = max_nft_reward_lamports;
+        }
+
+        if let Some(max_nft_apy_duration_days) = args.max_nft_apy_duration_days {
+            self.max_nft_apy_duration_days = max_nft_apy_duration_days;
+        }
+
+        if let Some(nft_days_apy) = args.nft_days_apy {
+            self.nft_days_apy = nft_days_apy;
+        }
+
+        if let Some(reward_rate) = args.reward_rate {
+            self.reward_rate = reward_rate;
+        }
+
+        if let Some(base_apy_tiers) = args.base_apy_tiers {
+            self.base_apy_tiers = base_apy_tiers;
+        }
+
+        if let Some(amount_brackets) = args.amount_brackets {
+            self.amount_brackets = amount_brackets;
+        }
+
+        if let Some(top_up_restarts_lock) = args.top_up_restarts_lock {
+            self.top_up_restarts_lock = top_up_restarts_lock;
+        }
+
+        if let Some(early_destake_penalty_bps) = args.early_destake_penalty_bps {
+            self.early_destake_penalty_bps = early_destake_penalty_bps;
+        }
+
+        if let Some(penalty_treasury) = args.penalty_treasury {
+            self.penalty_treasury = penalty_treasury;
+        }
+
+        if let Some(config_change_delay) = args.config_change_delay {
+            self.config_change_delay = config_change_delay;
+        }
+
+        if let Some(rarity_root) = args.rarity_root {
+            self.rarity_root = rarity_root;
+        }
+
+        if let Some(nft_stack_multipliers_bps) = args.nft_stack_multipliers_bps {
+            self.nft_stack_multipliers_bps = nft_stack_multipliers_bps;
+        }
+
+        if let Some(max_stacked_nft_apy) = args.max_stacked_nft_apy {
+            self.max_stacked_nft_apy = max_stacked_nft_apy;
+        }
     }
 }
 
+#[derive(Clone, Default, AnchorDeserialize, AnchorSerialize, InitSpace)]
+pub struct UpdateConfigArgs {
+    pub base_lock_days: Option<u16>,
+    pub max_nft_apy_duration_days: Option<u16>,
+    pub base_apy: Option<u16>,
+    pub max_nft_reward_lamports: Option<u64>,
+    #[max_len(MAX_NFT_TIERS)]
+    pub nft_days_apy: Option<Vec<NftApy>>,
+    pub reward_rate: Option<u64>,
+    #[max_len(MAX_BASE_APY_TIERS)]
+    pub base_apy_tiers: Option<Vec<BaseApyTier>>,
+    #[max_len(MAX_AMOUNT_BRACKETS)]
+    pub amount_brackets: Option<Vec<AmountBracket>>,
+    pub top_up_restarts_lock: Option<bool>,
+    pub early_destake_penalty_bps: Option<u16>,
+    pub penalty_treasury: Option<Pubkey>,
+    pub config_change_delay: Option<i64>,
+    pub rarity_root: Option<[u8; 32]>,
+    #[max_len(MAX_NFTS_PER_STAKE)]
+    pub nft_stack_multipliers_bps: Option<Vec<u16>>,
+    pub max_stacked_nft_apy: Option<u16>,
+}
+
+/// Proof that `multiplier_bps` is the rarity multiplier committed for a mint in
+/// `Config.rarity_root`.
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct RarityProof {
+    pub multiplier_bps: u16,
+    pub proof: Vec<[u8; 32]>,
+}
+
 #[account]
+#[derive(InitSpace)]
+pub struct PendingConfigChange {
+    pub version: u8,
+    pub proposer: Pubkey,
+    pub args: UpdateConfigArgs,
+    pub eligible_after: i64,
+    pub reserved: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VaultHealth {
+    pub vault_balance: u64,
+    pub reward_liability: u64,
+    pub total_staked: u64,
+    pub total_paid: u64,
+    pub legacy_stake_infos: u32,
+    pub surplus: u64,
+    pub shortfall: u64,
+}
+
+#[account]
+#[derive(Default, InitSpace)]
 pub struct StakeInfo {
+    pub version: u8,
     pub address: Pubkey,
+    pub next_stake_id: u64,
+    pub stake_count: u64,
+    pub reserved: [u8; 32],
+    // Entries created before stakes moved to their own accounts, drained by `migrate_stake_entry`
+    #[max_len(0)]
     pub stakes: Vec<StakeEntry>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+impl StakeInfo {
+    pub fn open_stake(&mut self) -> Result<u64> {
+        let stake_id = self.next_stake_id;
+
+        self.next_stake_id = self.next_stake_id
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        self.stake_count = self.stake_count
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        Ok(stake_id)
+    }
+
+    pub fn close_stake(&mut self) {
+        self.stake_count = self.stake_count.saturating_sub(1);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stake_count == 0 && self.stakes.is_empty()
+    }
+
+    // Legacy entries took their position as id and are only ever removed, so the list stays
+    // sorted by id
+    pub fn find_stake_position(&self, stake_id: u64) -> Result<usize> {
+        self.stakes
+            .binary_search_by_key(&stake_id, |stake_entry| stake_entry.id)
+            .map_err(|_| ErrorCode::InvalidStakeIndex.into())
+    }
+}
+
+// Outlives a closed stake info so the owner's stake ids keep counting when they stake again
+#[account]
+#[derive(InitSpace)]
+pub struct StakeCounter {
+    pub next_stake_id: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
 pub struct NftApy {
     pub days: u16,
     pub apy: u16,
+    pub enabled: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct BaseApyTier {
+    pub days: u16,
+    pub apy: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct AmountBracket {
+    pub min_amount: u64,
+    pub bonus_apy: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct LockedNft {
+    pub nft: Pubkey,
+    pub lock_time: i64,
+    pub lock_days: u16,
+    pub apy: u16,
+    pub unlock_time: Option<i64>,
+    pub max_reward_lamports: u64,
+    pub max_apy_duration_days: u16,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+#[account]
+#[derive(Default, InitSpace)]
 pub struct StakeEntry {
+    pub version: u8,
+    pub id: u64,
     pub amount: u64,
     pub start_time: i64,
     pub stake_lock_days: u16,
     pub base_apy: u16,
-    pub nft: Option<Pubkey>,
-    pub nft_lock_time: Option<i64>,
-    pub nft_lock_days: Option<u16>,
-    pub nft_apy: Option<u16>,
-    pub nft_unlock_time: Option<i64>,
+    // Part of `base_apy` granted by `Config.amount_brackets` for the staked amount
+    pub amount_bonus_apy: u16,
+    #[max_len(MAX_NFTS_PER_STAKE)]
+    pub nfts: Vec<LockedNft>,
     pub destake_time: Option<i64>,
     pub restake_time: Option<i64>,
-    pub parent_stake_index: Option<u64>,
+    pub parent_stake_id: Option<u64>,
     pub paid_amount: u64,
     pub max_nft_reward_lamports: u64,
     pub max_nft_apy_duration_days: u16,
+    pub reward_rate: u64,
+    // Share of `Config.reward_liability` committed by this stake, see `commit_reward_liability`
+    pub reward_liability: u64,
+    pub reward_accrual_per_year: u64,
+    pub liability_updated_at: i64,
+    pub reserved: [u8; 6],
 }
 
 impl StakeEntry {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         amount: u64,
         start_time: i64,
@@ -56,32 +380,57 @@ impl StakeEntry {
         base_apy: u16,
         max_nft_reward_lamports: u64,
         max_nft_apy_duration_days: u16,
-        parent_stake_index: Option<u64>
+        reward_rate: u64,
+        parent_stake_id: Option<u64>
     ) -> Self {
         Self {
+            version: STAKE_ENTRY_VERSION,
+            id: 0,
             amount,
             start_time,
             stake_lock_days,
             base_apy,
-            nft: None,
-            nft_lock_time: None,
-            nft_lock_days: None,
-            nft_apy: None,
-            nft_unlock_time: None,
+            amount_bonus_apy: 0,
+            nfts: Vec::new(),
             destake_time: None,
             restake_time: None,
-            parent_stake_index,
+            parent_stake_id,
             paid_amount: 0,
             max_nft_reward_lamports,
             max_nft_apy_duration_days,
+            reward_rate,
+            reward_liability: 0,
+            reward_accrual_per_year: 0,
+            liability_updated_at: 0,
+            reserved: [0; 6],
         }
     }
 
-    pub fn add_nft_info(&mut self, nft: Pubkey, lock_time: i64, lock_days: u16, apy: u16) {
-        self.nft = Some(nft);
-        self.nft_lock_time = Some(lock_time);
-        self.nft_lock_days = Some(lock_days);
-        self.nft_apy = Some(apy);
+    pub fn reward_liability_at(&self, current_time: i64) -> Result<u64> {
+        accrue_liability(
+            self.reward_liability,
+            self.reward_accrual_per_year,
+            self.liability_updated_at,
+            current_time
+        )
+    }
+
+    pub fn add_nft(&mut self, locked_nft: LockedNft) -> Result<()> {
+        require!(self.nfts.len() < MAX_NFTS_PER_STAKE, ErrorCode::NftStackFull);
+
+        self.nfts.push(locked_nft);
+
+        Ok(())
+    }
+
+    pub fn locked_nfts(&self) -> impl Iterator<Item = &LockedNft> {
+        self.nfts.iter().filter(|locked_nft| locked_nft.unlock_time.is_none())
+    }
+
+    pub fn find_locked_nft_mut(&mut self, nft: Pubkey) -> Option<&mut LockedNft> {
+        self.nfts
+            .iter_mut()
+            .find(|locked_nft| locked_nft.nft == nft && locked_nft.unlock_time.is_none())
     }
 
     pub fn add_payment(&mut self, payment: u64) {
@@ -89,13 +438,51 @@ impl StakeEntry {
     }
 
     pub fn is_nft_locked(&self) -> bool {
-        self.nft.is_some() && self.nft_lock_time.is_some() && self.nft_unlock_time.is_none()
+        self.locked_nfts().next().is_some()
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.destake_time.is_some() && !self.is_nft_locked()
     }
 }
 
+/// Lock schedule for a partner collection accepted alongside `Config.nft_collection`.
 #[account]
+#[derive(InitSpace)]
+pub struct NftCollection {
+    pub version: u8,
+    pub collection: Pubkey,
+    pub max_nft_reward_lamports: u64,
+    pub max_nft_apy_duration_days: u16,
+    #[max_len(MAX_NFT_TIERS)]
+    pub nft_days_apy: Vec<NftApy>,
+    pub reserved: [u8; 32],
+}
+
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct NftCollectionArgs {
+    pub max_nft_reward_lamports: u64,
+    pub max_nft_apy_duration_days: u16,
+    pub nft_days_apy: Vec<NftApy>,
+}
+
+/// Verified creators accepted in place of a verified collection, for NFTs minted
+/// before sized collections existed.
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorAllowList {
+    pub version: u8,
+    #[max_len(MAX_ALLOWED_CREATORS)]
+    pub creators: Vec<Pubkey>,
+    pub reserved: [u8; 32],
+}
+
+#[account]
+#[derive(InitSpace)]
 pub struct NftInfo {
+    pub version: u8,
     pub days_locked: u16,
+    pub reserved: [u8; 16],
 }
 
 impl NftInfo {
@@ -109,3 +496,20 @@ impl NftInfo {
         total_lock_days <= max_days
     }
 }
+
+fn accrue_liability(
+    liability: u64,
+    accrual_per_year: u64,
+    since: i64,
+    current_time: i64
+) -> Result<u64> {
+    let elapsed = current_time.saturating_sub(since).max(0) as u128;
+    let accrued = (accrual_per_year as u128)
+        .checked_mul(elapsed)
+        .map(|accrued| accrued / ((DAYS_PER_YEAR * SECONDS_PER_DAY) as u128))
+        .and_then(|accrued| u64::try_from(accrued).ok());
+
+    accrued
+        .and_then(|accrued| liability.checked_add(accrued))
+        .ok_or(ErrorCode::ArithmeticOverflow.into())
+}
@@ -1,71 +1,246 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use crate::{ state::NftApy, utils::* };
+    use std::collections::BTreeSet;
+    use anchor_lang::{
+        prelude::{ AccountInfo, Pubkey },
+        solana_program::{ program_pack::Pack, system_program },
+        Accounts,
+        AccountDeserialize,
+        AccountSerialize,
+        AnchorSerialize,
+        Discriminator,
+        Space,
+    };
+    use crate::{
+        constants::{
+            CONFIG_SEED,
+            DEFAULT_NFT_DAYS_APY,
+            MAX_AMOUNT_BONUS_APY,
+            MAX_AMOUNT_BRACKETS,
+            MAX_BASE_APY,
+            MAX_BASE_APY_TIERS,
+            MAX_NFTS_PER_STAKE,
+            MAX_NFT_APY,
+            MAX_NFT_TIERS,
+            NFT_COLLECTION_SEED,
+            STAKE_ENTRY_SEED,
+            TOKEN_SEED,
+        },
+        error::ErrorCode,
+        instructions::{
+            EmergencyWithdraw,
+            EmergencyWithdrawBumps,
+            RemoveNftCollection,
+            RemoveNftCollectionBumps,
+        },
+        legacy::*,
+        state::{
+            AmountBracket,
+            BaseApyTier,
+            Config,
+            LockedNft,
+            NftApy,
+            NftCollection,
+            NftInfo,
+            PendingConfigChange,
+            RarityProof,
+            StakeEntry,
+            StakeInfo,
+            UpdateConfigArgs,
+        },
+        utils::*,
+    };
+    use anchor_spl::{
+        associated_token::{ self, get_associated_token_address },
+        metadata::mpl_token_metadata::{
+            accounts::Metadata,
+            types::{ Collection, Creator, Key, TokenStandard },
+        },
+        token::spl_token,
+    };
     use chrono::NaiveDateTime;
 
     #[test]
     fn test_get_apy() {
         let nft_days_apy = [
-            NftApy { days: 30, apy: 2950 },
-            NftApy { days: 60, apy: 5950 },
-            NftApy { days: 90, apy: 10450 },
+            NftApy { days: 30, apy: 2950, enabled: true },
+            NftApy { days: 60, apy: 5950, enabled: true },
+            NftApy { days: 90, apy: 10450, enabled: true },
         ];
 
         // Test exact matches
-        assert_eq!(get_apy(30, nft_days_apy).unwrap(), 2950);
-        assert_eq!(get_apy(60, nft_days_apy).unwrap(), 5950);
-        assert_eq!(get_apy(90, nft_days_apy).unwrap(), 10450);
+        assert_eq!(get_apy(30, &nft_days_apy).unwrap(), 2950);
+        assert_eq!(get_apy(60, &nft_days_apy).unwrap(), 5950);
+        assert_eq!(get_apy(90, &nft_days_apy).unwrap(), 10450);
 
         // Test non-matching values
-        assert!(get_apy(29, nft_days_apy).is_err());
-        assert!(get_apy(31, nft_days_apy).is_err());
-        assert!(get_apy(59, nft_days_apy).is_err());
-        assert!(get_apy(61, nft_days_apy).is_err());
-        assert!(get_apy(89, nft_days_apy).is_err());
-        assert!(get_apy(91, nft_days_apy).is_err());
+        assert!(get_apy(29, &nft_days_apy).is_err());
+        assert!(get_apy(31, &nft_days_apy).is_err());
+        assert!(get_apy(59, &nft_days_apy).is_err());
+        assert!(get_apy(61, &nft_days_apy).is_err());
+        assert!(get_apy(89, &nft_days_apy).is_err());
+        assert!(get_apy(91, &nft_days_apy).is_err());
 
         // Test edge cases
-        assert!(get_apy(0, nft_days_apy).is_err());
-        assert!(get_apy(u16::MAX, nft_days_apy).is_err());
+        assert!(get_apy(0, &nft_days_apy).is_err());
+        assert!(get_apy(u16::MAX, &nft_days_apy).is_err());
     }
 
     #[test]
     fn test_get_apy_different_configurations() {
         let custom_nft_days_apy = [
-            NftApy { days: 7, apy: 1000 },
-            NftApy { days: 14, apy: 2000 },
-            NftApy { days: 21, apy: 3000 },
+            NftApy { days: 7, apy: 1000, enabled: true },
+            NftApy { days: 14, apy: 2000, enabled: true },
+            NftApy { days: 21, apy: 3000, enabled: true },
         ];
 
-        assert_eq!(get_apy(7, custom_nft_days_apy).unwrap(), 1000);
-        assert_eq!(get_apy(14, custom_nft_days_apy).unwrap(), 2000);
-        assert_eq!(get_apy(21, custom_nft_days_apy).unwrap(), 3000);
-        assert!(get_apy(28, custom_nft_days_apy).is_err());
+        assert_eq!(get_apy(7, &custom_nft_days_apy).unwrap(), 1000);
+        assert_eq!(get_apy(14, &custom_nft_days_apy).unwrap(), 2000);
+        assert_eq!(get_apy(21, &custom_nft_days_apy).unwrap(), 3000);
+        assert!(get_apy(28, &custom_nft_days_apy).is_err());
     }
 
     #[test]
     fn test_get_apy_unsorted_array() {
         let unsorted_nft_days_apy = [
-            NftApy { days: 90, apy: 10450 },
-            NftApy { days: 30, apy: 2950 },
-            NftApy { days: 60, apy: 5950 },
+            NftApy { days: 90, apy: 10450, enabled: true },
+            NftApy { days: 30, apy: 2950, enabled: true },
+            NftApy { days: 60, apy: 5950, enabled: true },
         ];
 
-        assert_eq!(get_apy(30, unsorted_nft_days_apy).unwrap(), 2950);
-        assert_eq!(get_apy(60, unsorted_nft_days_apy).unwrap(), 5950);
-        assert_eq!(get_apy(90, unsorted_nft_days_apy).unwrap(), 10450);
+        assert_eq!(get_apy(30, &unsorted_nft_days_apy).unwrap(), 2950);
+        assert_eq!(get_apy(60, &unsorted_nft_days_apy).unwrap(), 5950);
+        assert_eq!(get_apy(90, &unsorted_nft_days_apy).unwrap(), 10450);
     }
 
     #[test]
     fn test_get_apy_duplicate_days() {
         let duplicate_days_apy = [
-            NftApy { days: 30, apy: 2950 },
-            NftApy { days: 30, apy: 3000 },
-            NftApy { days: 60, apy: 5950 },
+            NftApy { days: 30, apy: 2950, enabled: true },
+            NftApy { days: 30, apy: 3000, enabled: true },
+            NftApy { days: 60, apy: 5950, enabled: true },
         ];
 
-        assert_eq!(get_apy(30, duplicate_days_apy).unwrap(), 2950);
-        assert_eq!(get_apy(60, duplicate_days_apy).unwrap(), 5950);
+        assert_eq!(get_apy(30, &duplicate_days_apy).unwrap(), 2950);
+        assert_eq!(get_apy(60, &duplicate_days_apy).unwrap(), 5950);
+    }
+
+    #[test]
+    fn test_get_apy_disabled_tier() {
+        let nft_days_apy = [
+            NftApy { days: 30, apy: 2950, enabled: true },
+            NftApy { days: 60, apy: 5950, enabled: false },
+        ];
+
+        assert_eq!(get_apy(30, &nft_days_apy).unwrap(), 2950);
+        assert!(get_apy(60, &nft_days_apy).is_err());
+    }
+
+    #[test]
+    fn test_validate_nft_tiers() {
+        let tier = |days, apy| NftApy { days, apy, enabled: true };
+
+        assert!(validate_nft_tiers(&[]).is_ok());
+        assert!(validate_nft_tiers(&[tier(7, 500), tier(14, 1000), tier(365, 20000)]).is_ok());
+
+        // Unsorted or duplicate days
+        assert!(validate_nft_tiers(&[tier(14, 1000), tier(7, 500)]).is_err());
+        assert!(validate_nft_tiers(&[tier(30, 2950), tier(30, 3000)]).is_err());
+
+        // Zero days or APY
+        assert!(validate_nft_tiers(&[tier(0, 500)]).is_err());
+        assert!(validate_nft_tiers(&[tier(7, 0)]).is_err());
+
+        // Too many tiers
+        let tiers: Vec<NftApy> = (1..=(MAX_NFT_TIERS as u16) + 1)
+            .map(|days| tier(days, 100))
+            .collect();
+        assert!(validate_nft_tiers(&tiers).is_err());
+        assert!(validate_nft_tiers(&tiers[..MAX_NFT_TIERS]).is_ok());
+    }
+
+    #[test]
+    fn test_manage_nft_tiers() {
+        let tier = |days, apy| NftApy { days, apy, enabled: true };
+        let mut nft_days_apy = DEFAULT_NFT_DAYS_APY.to_vec();
+
+        // Tiers are inserted in order of days
+        insert_nft_tier(&mut nft_days_apy, tier(7, 500)).unwrap();
+        insert_nft_tier(&mut nft_days_apy, tier(365, 20000)).unwrap();
+        insert_nft_tier(&mut nft_days_apy, tier(45, 4000)).unwrap();
+        let days: Vec<u16> = nft_days_apy.iter().map(|tier| tier.days).collect();
+        assert_eq!(days, vec![7, 30, 45, 60, 90, 365]);
+
+        // Existing tiers keep their APY
+        assert_eq!(get_apy(30, &nft_days_apy).unwrap(), 2950);
+        assert_eq!(get_apy(365, &nft_days_apy).unwrap(), 20000);
+
+        assert!(insert_nft_tier(&mut nft_days_apy, tier(30, 1000)).is_err());
+        assert!(insert_nft_tier(&mut nft_days_apy, tier(14, 0)).is_err());
+
+        set_nft_tier_enabled(&mut nft_days_apy, 45, false).unwrap();
+        assert!(get_apy(45, &nft_days_apy).is_err());
+        set_nft_tier_enabled(&mut nft_days_apy, 45, true).unwrap();
+        assert_eq!(get_apy(45, &nft_days_apy).unwrap(), 4000);
+        assert!(set_nft_tier_enabled(&mut nft_days_apy, 46, false).is_err());
+
+        assert_eq!(remove_nft_tier(&mut nft_days_apy, 45).unwrap().apy, 4000);
+        assert!(get_apy(45, &nft_days_apy).is_err());
+        assert!(remove_nft_tier(&mut nft_days_apy, 45).is_err());
+    }
+
+    #[test]
+    fn test_get_base_apy() {
+        let base_apy_tiers = [
+            BaseApyTier { days: 30, apy: 750 },
+            BaseApyTier { days: 90, apy: 1200 },
+            BaseApyTier { days: 180, apy: 1800 },
+        ];
+
+        // The default lock keeps the default APY
+        assert_eq!(get_base_apy(14, 14, 550, &base_apy_tiers).unwrap(), 550);
+        assert_eq!(get_base_apy(14, 14, 550, &[]).unwrap(), 550);
+
+        assert_eq!(get_base_apy(30, 14, 550, &base_apy_tiers).unwrap(), 750);
+        assert_eq!(get_base_apy(180, 14, 550, &base_apy_tiers).unwrap(), 1800);
+
+        assert!(get_base_apy(60, 14, 550, &base_apy_tiers).is_err());
+        assert!(get_base_apy(0, 14, 550, &base_apy_tiers).is_err());
+    }
+
+    #[test]
+    fn test_get_amount_bonus_apy() {
+        let amount_brackets = [
+            AmountBracket { min_amount: 10_000, bonus_apy: 50 },
+            AmountBracket { min_amount: 100_000, bonus_apy: 100 },
+        ];
+
+        assert_eq!(get_amount_bonus_apy(9_999, &amount_brackets), 0);
+        assert_eq!(get_amount_bonus_apy(10_000, &amount_brackets), 50);
+        assert_eq!(get_amount_bonus_apy(99_999, &amount_brackets), 50);
+        assert_eq!(get_amount_bonus_apy(100_000, &amount_brackets), 100);
+        assert_eq!(get_amount_bonus_apy(u64::MAX, &amount_brackets), 100);
+        assert_eq!(get_amount_bonus_apy(u64::MAX, &[]), 0);
+    }
+
+    #[test]
+    fn test_validate_base_apy_tiers_and_amount_brackets() {
+        let tier = |days, apy| BaseApyTier { days, apy };
+        let bracket = |min_amount, bonus_apy| AmountBracket { min_amount, bonus_apy };
+
+        assert!(validate_base_apy_tiers(&[tier(14, 550), tier(30, 750)]).is_ok());
+        assert!(validate_base_apy_tiers(&[tier(30, 750), tier(14, 550)]).is_err());
+        assert!(validate_base_apy_tiers(&[tier(30, 750), tier(30, 800)]).is_err());
+        assert!(validate_base_apy_tiers(&[tier(0, 750)]).is_err());
+        assert!(validate_base_apy_tiers(&[tier(30, 0)]).is_err());
+        assert!(validate_base_apy_tiers(&[tier(30, 750); MAX_BASE_APY_TIERS + 1]).is_err());
+
+        assert!(validate_amount_brackets(&[bracket(0, 10), bracket(1_000, 50)]).is_ok());
+        assert!(validate_amount_brackets(&[bracket(1_000, 50), bracket(0, 10)]).is_err());
+        assert!(validate_amount_brackets(&[bracket(1_000, 50), bracket(1_000, 60)]).is_err());
+        assert!(validate_amount_brackets(&[bracket(1_000, 0)]).is_err());
+        assert!(validate_amount_brackets(&[bracket(0, 10); MAX_AMOUNT_BRACKETS + 1]).is_err());
     }
 
     #[test]
@@ -235,6 +410,24 @@ mod tests {
         assert_eq!(calculate_reward(1, 1, 365), Some(0));
     }
 
+    fn new_stake_entry(amount: u64) -> StakeEntry {
+        StakeEntry::new(amount, 0, 14, 550, 750_000, 90, 1_000_000_000, None)
+    }
+
+    fn lock_test_nft(stake_entry: &mut StakeEntry, lock_time: i64, lock_days: u16, apy: u16) {
+        let locked_nft = LockedNft {
+            nft: Pubkey::new_unique(),
+            lock_time,
+            lock_days,
+            apy,
+            unlock_time: None,
+            max_reward_lamports: stake_entry.max_nft_reward_lamports,
+            max_apy_duration_days: stake_entry.max_nft_apy_duration_days,
+        };
+
+        stake_entry.add_nft(locked_nft).unwrap();
+    }
+
     fn date_to_timestamp(date_str: &str) -> i64 {
         NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S").unwrap().and_utc().timestamp()
     }
@@ -343,4 +536,1163 @@ mod tests {
 
         assert_eq!(to_lamports(123, 5).unwrap(), 12_300_000);
     }
+
+    #[test]
+    fn test_check_vault_surplus() {
+        // Anything above the liability can be withdrawn
+        assert!(check_vault_surplus(1_000, 400, 600).is_ok());
+        assert!(check_vault_surplus(1_000, 1_000, 0).is_ok());
+
+        // Withdrawals cannot dip into the liability
+        assert!(check_vault_surplus(1_000, 401, 600).is_err());
+
+        // Nor take more than the vault holds
+        assert!(check_vault_surplus(1_000, 1_001, 0).is_err());
+
+        // A vault already short of its liability has no surplus at all
+        assert!(check_vault_surplus(500, 1, 600).is_err());
+    }
+
+    #[test]
+    fn test_calculate_committed_reward() {
+        let day = 86_400;
+        let mut stake_entry = new_stake_entry(100_000);
+
+        // Base reward through the lock: 100_000 * 5.5% * 14 / 365
+        assert_eq!(calculate_committed_reward(&stake_entry, 0).unwrap(), 210);
+
+        // After the lock the base reward is committed through the current day
+        assert_eq!(
+            calculate_committed_reward(&stake_entry, 20 * day + 1).unwrap(),
+            calculate_reward(100_000, 550, 21).unwrap()
+        );
+
+        // NFT rewards are committed through their max APY duration, not their lock
+        lock_test_nft(&mut stake_entry, 0, 30, 2950);
+        assert_eq!(calculate_committed_reward(&stake_entry, 0).unwrap(), 210 + 7273);
+
+        // NFT reward is capped by max_reward_lamports
+        stake_entry.nfts[0].max_reward_lamports = 1_000;
+        assert_eq!(calculate_committed_reward(&stake_entry, 0).unwrap(), 210 + 1_000);
+
+        // NFT days are capped by max_apy_duration_days
+        stake_entry.nfts[0].max_reward_lamports = 750_000;
+        stake_entry.nfts[0].max_apy_duration_days = 10;
+        assert_eq!(calculate_committed_reward(&stake_entry, 0).unwrap(), 210 + 808);
+    }
+
+    #[test]
+    fn test_calculate_outstanding_reward() {
+        let mut stake_entry = new_stake_entry(100_000);
+        assert_eq!(calculate_outstanding_reward(&stake_entry, 0).unwrap(), 210);
+
+        stake_entry.add_payment(110);
+        assert_eq!(calculate_outstanding_reward(&stake_entry, 0).unwrap(), 100);
+
+        // Payments beyond the projection leave no outstanding liability
+        stake_entry.add_payment(1_000);
+        assert_eq!(calculate_outstanding_reward(&stake_entry, 0).unwrap(), 0);
+
+        // Destaked entries carry no liability
+        let mut destaked_entry = new_stake_entry(100_000);
+        destaked_entry.destake_time = Some(0);
+        assert_eq!(calculate_outstanding_reward(&destaked_entry, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reward_liability_covers_rewards_after_the_lock() {
+        let day = 86_400;
+        let mut config = test_config();
+        let mut stake_entry = new_stake_entry(100_000_000);
+        lock_test_nft(&mut stake_entry, 0, 30, 2950);
+
+        commit_reward_liability(&mut config, &mut stake_entry, 0).unwrap();
+        let annual_base_reward = calculate_annual_base_reward(&stake_entry).unwrap();
+        assert_eq!(config.reward_accrual_per_year, annual_base_reward);
+
+        // The liability keeps up with the reward well past the lock and the NFT duration
+        for days in [14, 90, 365, 3 * 365] {
+            let current_time = days * day;
+            let liability = config.reward_liability_at(current_time).unwrap();
+
+            assert!(liability >= calculate_claimable_reward(&stake_entry, current_time).unwrap());
+        }
+
+        // Claiming moves the paid reward out of the liability
+        let current_time = 400 * day;
+        let liability = config.reward_liability_at(current_time).unwrap();
+        let claimable_reward = calculate_claimable_reward(&stake_entry, current_time).unwrap();
+
+        release_reward_liability(&mut config, &mut stake_entry, current_time).unwrap();
+        stake_entry.add_payment(claimable_reward);
+        commit_reward_liability(&mut config, &mut stake_entry, current_time).unwrap();
+
+        let after_claim = config.reward_liability_at(current_time).unwrap();
+        assert!(after_claim <= liability - claimable_reward);
+        assert!(after_claim >= calculate_reward(100_000_000, 550, 1).unwrap());
+
+        // Destaking releases everything the stake committed
+        let current_time = 500 * day;
+        stake_entry.destake_time = Some(current_time);
+        release_reward_liability(&mut config, &mut stake_entry, current_time).unwrap();
+        commit_reward_liability(&mut config, &mut stake_entry, current_time).unwrap();
+
+        assert_eq!(config.reward_liability_at(current_time).unwrap(), 0);
+        assert_eq!(config.reward_liability_at(600 * day).unwrap(), 0);
+        assert_eq!(config.reward_accrual_per_year, 0);
+    }
+
+    #[test]
+    fn test_convert_to_reward() {
+        // Same token: identity rate
+        assert_eq!(convert_to_reward(123_456, 1_000_000_000), Some(123_456));
+
+        // Half a reward token per staked token
+        assert_eq!(convert_to_reward(1_000, 500_000_000), Some(500));
+
+        // Stake token with 9 decimals, reward token with 6 decimals, 1:1
+        assert_eq!(convert_to_reward(1_000_000_000, 1_000_000), Some(1_000_000));
+
+        // Rounds down
+        assert_eq!(convert_to_reward(1, 500_000_000), Some(0));
+
+        assert_eq!(convert_to_reward(0, 1_000_000_000), Some(0));
+        assert_eq!(convert_to_reward(u64::MAX, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_default_reward_rate() {
+        assert_eq!(default_reward_rate(9, 9).unwrap(), 1_000_000_000);
+        assert_eq!(default_reward_rate(9, 6).unwrap(), 1_000_000);
+        assert_eq!(default_reward_rate(6, 9).unwrap(), 1_000_000_000_000);
+        assert_eq!(default_reward_rate(0, 9).unwrap(), 1_000_000_000_000_000_000);
+
+        assert!(default_reward_rate(0, 11).is_err());
+        assert!(default_reward_rate(19, 0).is_err());
+    }
+
+    #[test]
+    fn test_calculate_claimable_reward_with_reward_rate() {
+        let one_year = 365 * 86_400;
+
+        // 10% APY on 1_000 staked tokens pays 100 at a 1:1 rate
+        let stake_entry = StakeEntry::new(1_000, 0, 14, 1000, 0, 90, 1_000_000_000, None);
+        assert_eq!(calculate_claimable_reward(&stake_entry, one_year).unwrap(), 100);
+
+        // and 250 reward units at 2.5 reward units per staked unit
+        let stake_entry = StakeEntry::new(1_000, 0, 14, 1000, 0, 90, 2_500_000_000, None);
+        assert_eq!(calculate_claimable_reward(&stake_entry, one_year).unwrap(), 250);
+    }
+
+    #[test]
+    fn test_add_principal_resets_reward_basis() {
+        let one_year = 365 * 86_400;
+
+        // 1_000 tokens at 10% APY earn 100 in the first year
+        let mut stake_entry = StakeEntry::new(1_000, 0, 14, 1000, 0, 90, 1_000_000_000, None);
+        let reward = calculate_claimable_reward(&stake_entry, one_year).unwrap();
+        assert_eq!(reward, 100);
+
+        // Compounding the reward keeps the start time but nothing is claimable right after
+        stake_entry.add_payment(reward);
+        add_principal(&mut stake_entry, reward, one_year).unwrap();
+        assert_eq!(stake_entry.amount, 1_100);
+        assert_eq!(stake_entry.start_time, 0);
+        assert_eq!(calculate_claimable_reward(&stake_entry, one_year).unwrap(), 0);
+
+        // The second year accrues on the compounded principal
+        assert_eq!(calculate_claimable_reward(&stake_entry, 2 * one_year).unwrap(), 110);
+    }
+
+    #[test]
+    fn test_add_principal_with_capped_nft_reward() {
+        let day = 86_400;
+
+        let mut stake_entry = StakeEntry::new(100_000, 0, 14, 0, 1_000, 90, 1_000_000_000, None);
+        lock_test_nft(&mut stake_entry, 0, 90, 10000);
+
+        // The NFT reward hits its cap after a few days
+        let reward = calculate_claimable_reward(&stake_entry, 10 * day).unwrap();
+        assert_eq!(reward, 1_000);
+
+        stake_entry.add_payment(reward);
+        add_principal(&mut stake_entry, reward, 10 * day).unwrap();
+
+        // Additional principal cannot earn past the cap
+        assert_eq!(calculate_claimable_reward(&stake_entry, 10 * day).unwrap(), 0);
+        assert_eq!(calculate_claimable_reward(&stake_entry, 90 * day).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_remove_principal_resets_reward_basis() {
+        let one_year = 365 * 86_400;
+
+        // 1_000 tokens at 10% APY earn 100 in the first year
+        let mut stake_entry = StakeEntry::new(1_000, 0, 14, 1000, 0, 90, 1_000_000_000, None);
+        assert_eq!(calculate_claimable_reward(&stake_entry, one_year).unwrap(), 100);
+
+        // Settling the reward first leaves nothing claimable on the remainder
+        stake_entry.add_payment(100);
+        remove_principal(&mut stake_entry, 400, &[], one_year).unwrap();
+        assert_eq!(stake_entry.amount, 600);
+        assert_eq!(calculate_claimable_reward(&stake_entry, one_year).unwrap(), 0);
+
+        // The second year accrues on the remaining principal only
+        assert_eq!(calculate_claimable_reward(&stake_entry, 2 * one_year).unwrap(), 60);
+    }
+
+    #[test]
+    fn test_remove_principal_scales_nft_reward_cap() {
+        let day = 86_400;
+
+        let mut stake_entry = StakeEntry::new(100_000, 0, 14, 0, 1_000, 90, 1_000_000_000, None);
+        lock_test_nft(&mut stake_entry, 0, 90, 10000);
+
+        remove_principal(&mut stake_entry, 25_000, &[], 0).unwrap();
+        assert_eq!(stake_entry.max_nft_reward_lamports, 750);
+        assert_eq!(stake_entry.nfts[0].max_reward_lamports, 750);
+        assert_eq!(calculate_claimable_reward(&stake_entry, 90 * day).unwrap(), 750);
+    }
+
+    #[test]
+    fn test_remove_principal_recomputes_amount_bonus() {
+        let one_year = 365 * 86_400;
+        let brackets = [
+            AmountBracket { min_amount: 1_000, bonus_apy: 100 },
+            AmountBracket { min_amount: 10_000, bonus_apy: 300 },
+        ];
+
+        // 10% for the lock plus the 3% bonus of the top bracket
+        let mut stake_entry = StakeEntry::new(10_000, 0, 14, 1300, 0, 90, 1_000_000_000, None);
+        stake_entry.amount_bonus_apy = 300;
+
+        // The remainder falls in the lower bracket
+        remove_principal(&mut stake_entry, 5_000, &brackets, 0).unwrap();
+        assert_eq!(stake_entry.base_apy, 1100);
+        assert_eq!(stake_entry.amount_bonus_apy, 100);
+        assert_eq!(
+            calculate_claimable_reward(&stake_entry, one_year).unwrap(),
+            calculate_reward(5_000, 1100, 365).unwrap()
+        );
+
+        // Below every bracket only the lock APY is left
+        remove_principal(&mut stake_entry, 4_500, &brackets, 0).unwrap();
+        assert_eq!(stake_entry.base_apy, 1000);
+        assert_eq!(stake_entry.amount_bonus_apy, 0);
+
+        // Brackets raised after the stake was opened don't add to it
+        let mut stake_entry = StakeEntry::new(10_000, 0, 14, 1100, 0, 90, 1_000_000_000, None);
+        stake_entry.amount_bonus_apy = 100;
+        remove_principal(&mut stake_entry, 1_000, &brackets, 0).unwrap();
+        assert_eq!(stake_entry.base_apy, 1100);
+    }
+
+    #[test]
+    fn test_remove_principal_rejects_full_amount() {
+        let mut stake_entry = new_stake_entry(1_000);
+
+        assert!(remove_principal(&mut stake_entry, 1_000, &[], 0).is_err());
+        assert!(remove_principal(&mut stake_entry, 1_001, &[], 0).is_err());
+        assert_eq!(stake_entry.amount, 1_000);
+    }
+
+    #[test]
+    fn test_restart_lock_after_settling_reward() {
+        let day = 86_400;
+        let one_year = 365 * day;
+
+        let mut stake_entry = StakeEntry::new(1_000, 0, 14, 1000, 0, 90, 1_000_000_000, None);
+        assert!(restart_lock(&mut stake_entry, one_year).is_err());
+
+        stake_entry.add_payment(calculate_claimable_reward(&stake_entry, one_year).unwrap());
+        restart_lock(&mut stake_entry, one_year).unwrap();
+        assert_eq!(stake_entry.start_time, one_year);
+        assert_eq!(calculate_claimable_reward(&stake_entry, one_year).unwrap(), 0);
+
+        // The base lock runs again from the restart
+        assert!(check_lock_periods_ended(&stake_entry, one_year + 13 * day).is_err());
+        assert!(check_lock_periods_ended(&stake_entry, one_year + 14 * day).is_ok());
+
+        add_principal(&mut stake_entry, 1_000, one_year).unwrap();
+        assert_eq!(calculate_claimable_reward(&stake_entry, 2 * one_year).unwrap(), 200);
+    }
+
+    #[test]
+    fn test_restart_lock_keeps_nft_reward_basis() {
+        let day = 86_400;
+
+        let mut stake_entry = StakeEntry::new(100_000, 0, 14, 0, 1_000, 90, 1_000_000_000, None);
+        lock_test_nft(&mut stake_entry, 0, 90, 10000);
+
+        let reward = calculate_claimable_reward(&stake_entry, 10 * day).unwrap();
+        stake_entry.add_payment(reward);
+        restart_lock(&mut stake_entry, 10 * day).unwrap();
+
+        // The NFT reward already paid out is not paid again
+        assert_eq!(calculate_claimable_reward(&stake_entry, 90 * day).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_calculate_penalty() {
+        assert_eq!(calculate_penalty(1_000_000, 0), Some(0));
+        assert_eq!(calculate_penalty(1_000_000, 250), Some(25_000));
+        assert_eq!(calculate_penalty(1_000_000, 10_000), Some(1_000_000));
+        assert_eq!(calculate_penalty(999, 1), Some(0));
+        assert_eq!(calculate_penalty(u64::MAX, 10_000), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_stake_entry_is_settled() {
+        let mut stake_entry = new_stake_entry(1_000);
+        assert!(!stake_entry.is_settled());
+
+        lock_test_nft(&mut stake_entry, 0, 30, 2950);
+        stake_entry.destake_time = Some(0);
+        assert!(!stake_entry.is_settled());
+
+        stake_entry.nfts[0].unlock_time = Some(0);
+        assert!(stake_entry.is_settled());
+    }
+
+    #[test]
+    fn test_stacked_nfts_reward_independently() {
+        let day = 86_400;
+
+        let mut stake_entry = new_stake_entry(100_000);
+        lock_test_nft(&mut stake_entry, 0, 30, 2950);
+        lock_test_nft(&mut stake_entry, 10 * day, 30, 1475);
+
+        // Each NFT accrues from its own lock time
+        assert_eq!(calculate_committed_reward(&stake_entry, 0).unwrap(), 210 + 7273 + 3636);
+        assert_eq!(
+            calculate_claimable_reward(&stake_entry, 20 * day).unwrap(),
+            calculate_reward(100_000, 550, 20).unwrap() +
+                calculate_reward(100_000, 2950, 20).unwrap() +
+                calculate_reward(100_000, 1475, 10).unwrap()
+        );
+
+        // Every NFT has to finish its lock period
+        assert!(check_lock_periods_ended(&stake_entry, 30 * day).is_err());
+        assert!(check_lock_periods_ended(&stake_entry, 40 * day).is_ok());
+    }
+
+    #[test]
+    fn test_nft_stack_multipliers() {
+        let mut config = test_config();
+
+        // Without stacking rules a stake holds a single NFT at 1x
+        assert_eq!(config.nft_stack_multiplier_bps(0), Some(10_000));
+        assert_eq!(config.nft_stack_multiplier_bps(1), None);
+
+        config.nft_stack_multipliers_bps = vec![10_000, 5_000, 2_500];
+        assert_eq!(config.nft_stack_multiplier_bps(1), Some(5_000));
+        assert_eq!(config.nft_stack_multiplier_bps(3), None);
+        assert!(validate_config(&config).is_ok());
+
+        // Stacked NFTs get a share of the reward cap in line with their boost
+        assert_eq!(scale_nft_reward_cap(750_000, 10_000).unwrap(), 750_000);
+        assert_eq!(scale_nft_reward_cap(750_000, 5_000).unwrap(), 375_000);
+        assert_eq!(scale_nft_reward_cap(750_000, 2_500).unwrap(), 187_500);
+
+        assert!(validate_nft_stack_multipliers(&[10_000, 12_000]).is_err());
+        assert!(validate_nft_stack_multipliers(&[5_000, 10_000]).is_err());
+        assert!(validate_nft_stack_multipliers(&[10_000, 0]).is_err());
+        assert!(validate_nft_stack_multipliers(&[10_000; MAX_NFTS_PER_STAKE + 1]).is_err());
+    }
+
+    #[test]
+    fn test_cap_stacked_nft_apy() {
+        let mut stake_entry = new_stake_entry(100_000);
+        assert_eq!(cap_stacked_nft_apy(2950, &stake_entry, 0).unwrap(), 2950);
+        assert_eq!(cap_stacked_nft_apy(2950, &stake_entry, 4000).unwrap(), 2950);
+
+        lock_test_nft(&mut stake_entry, 0, 30, 2950);
+        assert_eq!(cap_stacked_nft_apy(2950, &stake_entry, 4000).unwrap(), 1050);
+        assert_eq!(cap_stacked_nft_apy(2950, &stake_entry, 0).unwrap(), 2950);
+
+        lock_test_nft(&mut stake_entry, 0, 30, 1050);
+        assert!(cap_stacked_nft_apy(2950, &stake_entry, 4000).is_err());
+
+        // Unlocked NFTs no longer count towards the cap
+        stake_entry.nfts[1].unlock_time = Some(0);
+        assert_eq!(cap_stacked_nft_apy(2950, &stake_entry, 4000).unwrap(), 1050);
+    }
+
+    #[test]
+    fn test_stake_entry_holds_bounded_nft_set() {
+        let mut stake_entry = new_stake_entry(1_000);
+        for _ in 0..MAX_NFTS_PER_STAKE {
+            lock_test_nft(&mut stake_entry, 0, 30, 2950);
+        }
+
+        assert!(stake_entry.add_nft(LockedNft::default()).is_err());
+
+        let nft = stake_entry.nfts[2].nft;
+        stake_entry.find_locked_nft_mut(nft).unwrap().unlock_time = Some(0);
+        assert!(stake_entry.find_locked_nft_mut(nft).is_none());
+        assert_eq!(stake_entry.locked_nfts().count(), MAX_NFTS_PER_STAKE - 1);
+    }
+
+    #[test]
+    fn test_stake_ids_survive_closing() {
+        let mut stake_info = StakeInfo::default();
+
+        for expected_id in 0..3 {
+            assert_eq!(stake_info.open_stake().unwrap(), expected_id);
+        }
+        assert_eq!(stake_info.stake_count, 3);
+
+        stake_info.close_stake();
+        assert_eq!(stake_info.open_stake().unwrap(), 3);
+        assert_eq!(stake_info.stake_count, 3);
+        assert!(!stake_info.is_empty());
+
+        for _ in 0..3 {
+            stake_info.close_stake();
+        }
+        assert!(stake_info.is_empty());
+        assert_eq!(stake_info.next_stake_id, 4);
+    }
+
+    #[test]
+    fn test_find_legacy_stake_position() {
+        let stakes = [0, 2, 3].map(|id| StakeEntry { id, ..Default::default() });
+        let stake_info = StakeInfo {
+            next_stake_id: 4,
+            stakes: stakes.to_vec(),
+            ..Default::default()
+        };
+
+        assert_eq!(stake_info.find_stake_position(0).unwrap(), 0);
+        assert_eq!(stake_info.find_stake_position(3).unwrap(), 2);
+        assert!(stake_info.find_stake_position(1).is_err());
+        assert!(!stake_info.is_empty());
+    }
+
+    #[test]
+    fn test_stake_entry_init_space_matches_serialized_len() {
+        let mut stake_entry = new_stake_entry(1_000);
+        for _ in 0..MAX_NFTS_PER_STAKE {
+            lock_test_nft(&mut stake_entry, 0, 30, 2950);
+        }
+        for locked_nft in stake_entry.nfts.iter_mut() {
+            locked_nft.unlock_time = Some(0);
+        }
+        stake_entry.destake_time = Some(0);
+        stake_entry.restake_time = Some(0);
+        stake_entry.parent_stake_id = Some(0);
+
+        assert_eq!(stake_entry.try_to_vec().unwrap().len(), StakeEntry::INIT_SPACE);
+    }
+
+    #[test]
+    fn test_stake_info_init_space_matches_empty_header() {
+        let stake_info = StakeInfo::default();
+
+        assert_eq!(stake_info.try_to_vec().unwrap().len(), StakeInfo::INIT_SPACE);
+    }
+
+    #[test]
+    fn test_pending_config_change_fits_largest_args() {
+        let pending = PendingConfigChange {
+            version: 1,
+            proposer: Pubkey::new_unique(),
+            args: UpdateConfigArgs {
+                base_lock_days: Some(30),
+                max_nft_apy_duration_days: Some(365),
+                base_apy: Some(500),
+                max_nft_reward_lamports: Some(1_000_000),
+                nft_days_apy: Some(vec![NftApy::default(); MAX_NFT_TIERS]),
+                reward_rate: Some(1_000_000),
+                base_apy_tiers: Some(vec![BaseApyTier::default(); MAX_BASE_APY_TIERS]),
+                amount_brackets: Some(vec![AmountBracket::default(); MAX_AMOUNT_BRACKETS]),
+                top_up_restarts_lock: Some(true),
+                early_destake_penalty_bps: Some(250),
+                penalty_treasury: Some(Pubkey::new_unique()),
+                config_change_delay: Some(86_400),
+                rarity_root: Some([1; 32]),
+                nft_stack_multipliers_bps: Some(vec![10_000; MAX_NFTS_PER_STAKE]),
+                max_stacked_nft_apy: Some(20_000),
+            },
+            eligible_after: 86_400,
+            reserved: [0; 32],
+        };
+
+        assert_eq!(pending.try_to_vec().unwrap().len(), PendingConfigChange::INIT_SPACE);
+    }
+
+    #[test]
+    fn test_pause_flags() {
+        let mut config = test_config();
+        assert!(!config.is_staking_paused());
+        assert!(!config.is_nft_locking_paused());
+        assert!(!config.is_claims_paused());
+
+        // Each flag pauses its own instructions only
+        config.staking_paused = true;
+        assert!(config.is_staking_paused());
+        assert!(!config.is_nft_locking_paused());
+        assert!(!config.is_claims_paused());
+
+        config.staking_paused = false;
+        config.nft_locking_paused = true;
+        assert!(!config.is_staking_paused());
+        assert!(config.is_nft_locking_paused());
+        assert!(!config.is_claims_paused());
+
+        config.nft_locking_paused = false;
+        config.claims_paused = true;
+        assert!(!config.is_staking_paused());
+        assert!(!config.is_nft_locking_paused());
+        assert!(config.is_claims_paused());
+
+        // Emergency mode pauses everything but emergency withdrawals
+        config.claims_paused = false;
+        config.emergency_mode = true;
+        assert!(config.is_staking_paused());
+        assert!(config.is_nft_locking_paused());
+        assert!(config.is_claims_paused());
+    }
+
+    // Accounts handed to `try_accounts` live for the rest of the test run
+    fn test_account_info(
+        key: Pubkey,
+        owner: Pubkey,
+        data: Vec<u8>,
+        is_signer: bool,
+        executable: bool
+    ) -> AccountInfo<'static> {
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            is_signer,
+            !executable,
+            Box::leak(Box::new(1_000_000_000)),
+            Box::leak(data.into_boxed_slice()),
+            Box::leak(Box::new(owner)),
+            executable,
+            0
+        )
+    }
+
+    fn program_account_data(account: &impl AccountSerialize) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn token_account_data(mint: Pubkey, owner: Pubkey) -> Vec<u8> {
+        let token_account = spl_token::state::Account {
+            mint,
+            owner,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        token_account.pack_into_slice(&mut data);
+        data
+    }
+
+    fn mint_data(decimals: u8) -> Vec<u8> {
+        let mint = spl_token::state::Mint { decimals, is_initialized: true, ..Default::default() };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        data
+    }
+
+    fn emergency_withdraw_accounts(
+        config: &Config,
+        mint: Pubkey
+    ) -> &'static [AccountInfo<'static>] {
+        let signer = Pubkey::new_unique();
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID).0;
+        let stake_account = pda(&[TOKEN_SEED, signer.as_ref()]);
+
+        vec![
+            test_account_info(signer, system_program::ID, Vec::new(), true, false),
+            test_account_info(
+                pda(&[CONFIG_SEED]),
+                crate::ID,
+                program_account_data(config),
+                false,
+                false
+            ),
+            test_account_info(
+                pda(&[STAKE_ENTRY_SEED, signer.as_ref(), &(0u64).to_le_bytes()]),
+                crate::ID,
+                program_account_data(&new_stake_entry(100_000)),
+                false,
+                false
+            ),
+            test_account_info(
+                stake_account,
+                spl_token::ID,
+                token_account_data(mint, stake_account),
+                false,
+                false
+            ),
+            test_account_info(
+                get_associated_token_address(&signer, &mint),
+                spl_token::ID,
+                token_account_data(mint, signer),
+                false,
+                false
+            ),
+            test_account_info(mint, spl_token::ID, mint_data(9), false, false),
+            test_account_info(spl_token::ID, Pubkey::default(), Vec::new(), false, true),
+            test_account_info(associated_token::ID, Pubkey::default(), Vec::new(), false, true),
+            test_account_info(system_program::ID, Pubkey::default(), Vec::new(), false, true)
+        ].leak()
+    }
+
+    #[test]
+    fn test_staking_mint_is_pinned() {
+        let config = test_config();
+        let try_accounts = |mint| {
+            EmergencyWithdraw::try_accounts(
+                &crate::ID,
+                &mut emergency_withdraw_accounts(&config, mint),
+                &(0u64).to_le_bytes(),
+                &mut EmergencyWithdrawBumps::default(),
+                &mut BTreeSet::new()
+            ).map(|_| ())
+        };
+
+        assert!(try_accounts(config.mint).is_ok());
+
+        // Any other mint is rejected, even with matching token accounts
+        assert_eq!(try_accounts(Pubkey::new_unique()).unwrap_err(), ErrorCode::InvalidMint.into());
+    }
+
+    #[test]
+    fn test_nft_collection_removal_is_timelocked() {
+        let try_accounts = |config: &Config| {
+            let collection = Pubkey::new_unique();
+            let nft_collection = NftCollection {
+                version: 1,
+                collection,
+                max_nft_reward_lamports: 750_000,
+                max_nft_apy_duration_days: 90,
+                nft_days_apy: Vec::new(),
+                reserved: [0; 32],
+            };
+            let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID).0;
+            let accounts = vec![
+                test_account_info(
+                    pda(&[CONFIG_SEED]),
+                    crate::ID,
+                    program_account_data(config),
+                    false,
+                    false
+                ),
+                test_account_info(
+                    pda(&[NFT_COLLECTION_SEED, collection.as_ref()]),
+                    crate::ID,
+                    program_account_data(&nft_collection),
+                    false,
+                    false
+                ),
+                test_account_info(
+                    config.config_manager,
+                    system_program::ID,
+                    Vec::new(),
+                    true,
+                    false
+                )
+            ];
+
+            RemoveNftCollection::try_accounts(
+                &crate::ID,
+                &mut &*accounts.leak(),
+                &[],
+                &mut RemoveNftCollectionBumps::default(),
+                &mut BTreeSet::new()
+            ).map(|_| ())
+        };
+
+        let mut config = test_config();
+        assert!(try_accounts(&config).is_ok());
+
+        // Otherwise the collection could be removed and added again with a new schedule
+        config.config_change_delay = 86_400;
+        assert_eq!(
+            try_accounts(&config).unwrap_err(),
+            ErrorCode::ConfigChangeTimelocked.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let valid = test_config();
+        assert!(validate_config(&valid).is_ok());
+
+        let rejects = |args: UpdateConfigArgs| {
+            let mut config = valid.clone();
+            config.apply_update(args);
+            validate_config(&config).is_err()
+        };
+
+        assert!(rejects(UpdateConfigArgs { base_apy: Some(0), ..Default::default() }));
+        assert!(rejects(UpdateConfigArgs { base_apy: Some(u16::MAX), ..Default::default() }));
+        assert!(rejects(UpdateConfigArgs { base_lock_days: Some(0), ..Default::default() }));
+        assert!(rejects(UpdateConfigArgs { reward_rate: Some(0), ..Default::default() }));
+        assert!(rejects(UpdateConfigArgs { config_change_delay: Some(-1), ..Default::default() }));
+
+        // Tiers must stay lockable within the max duration
+        assert!(
+            rejects(UpdateConfigArgs { max_nft_apy_duration_days: Some(60), ..Default::default() })
+        );
+
+        // Base tiers must not collide with the base lock days
+        assert!(rejects(UpdateConfigArgs { base_lock_days: Some(30), ..Default::default() }));
+
+        assert!(
+            !rejects(UpdateConfigArgs {
+                base_lock_days: Some(7),
+                max_nft_apy_duration_days: Some(90),
+                config_change_delay: Some(86_400),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_tier_caps() {
+        let nft_tier = |days, apy| NftApy { days, apy, enabled: true };
+
+        assert!(validate_nft_tiers(&[nft_tier(30, MAX_NFT_APY)]).is_ok());
+        assert!(validate_nft_tiers(&[nft_tier(30, MAX_NFT_APY + 1)]).is_err());
+        assert!(validate_nft_tiers(&[nft_tier(30, 2950), nft_tier(30, 2950)]).is_err());
+
+        assert!(validate_base_apy_tiers(&[BaseApyTier { days: 30, apy: MAX_BASE_APY }]).is_ok());
+        assert!(
+            validate_base_apy_tiers(&[BaseApyTier { days: 30, apy: MAX_BASE_APY + 1 }]).is_err()
+        );
+
+        let bracket = |bonus_apy| AmountBracket { min_amount: 0, bonus_apy };
+        assert!(validate_amount_brackets(&[bracket(MAX_AMOUNT_BONUS_APY)]).is_ok());
+        assert!(validate_amount_brackets(&[bracket(MAX_AMOUNT_BONUS_APY + 1)]).is_err());
+    }
+
+    #[test]
+    fn test_validate_nft_schedule() {
+        let tiers = DEFAULT_NFT_DAYS_APY.to_vec();
+
+        assert!(validate_nft_schedule(&tiers, 90).is_ok());
+        assert!(validate_nft_schedule(&tiers, 60).is_err());
+        assert!(validate_nft_schedule(&[], 0).is_err());
+    }
+
+    #[test]
+    fn test_nft_collection_fits_max_tiers() {
+        let nft_collection = NftCollection {
+            version: 1,
+            collection: Pubkey::new_unique(),
+            max_nft_reward_lamports: 750_000,
+            max_nft_apy_duration_days: 90,
+            nft_days_apy: vec![NftApy::default(); MAX_NFT_TIERS],
+            reserved: [0; 32],
+        };
+
+        assert_eq!(nft_collection.try_to_vec().unwrap().len(), NftCollection::INIT_SPACE);
+    }
+
+    // Builds the rarity tree bottom up and returns its root with the proof for each leaf
+    fn rarity_tree(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+        let mut proofs = vec![Vec::new(); leaves.len()];
+        let mut positions: Vec<usize> = (0..leaves.len()).collect();
+        let mut level = leaves.to_vec();
+
+        while level.len() > 1 {
+            for (leaf, position) in positions.iter_mut().enumerate() {
+                if let Some(sibling) = level.get(*position ^ 1) {
+                    proofs[leaf].push(*sibling);
+                }
+                *position /= 2;
+            }
+
+            level = level
+                .chunks(2)
+                .map(|pair| if pair.len() == 2 { rarity_node(&pair[0], &pair[1]) } else { pair[0] })
+                .collect();
+        }
+
+        (level[0], proofs)
+    }
+
+    #[test]
+    fn test_verify_rarity_proof() {
+        let mints: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let multipliers = [10_000, 12_500, 15_000, 20_000, 30_000];
+        let leaves: Vec<[u8; 32]> = mints
+            .iter()
+            .zip(multipliers)
+            .map(|(mint, multiplier)| rarity_leaf(mint, multiplier))
+            .collect();
+        let (root, proofs) = rarity_tree(&leaves);
+
+        for (i, mint) in mints.iter().enumerate() {
+            assert!(verify_rarity_proof(&root, mint, multipliers[i], &proofs[i]));
+        }
+
+        // Claiming a higher multiplier or another mint's proof fails
+        assert!(!verify_rarity_proof(&root, &mints[0], 30_000, &proofs[0]));
+        assert!(!verify_rarity_proof(&root, &mints[0], multipliers[1], &proofs[1]));
+        assert!(!verify_rarity_proof(&root, &Pubkey::new_unique(), multipliers[0], &proofs[0]));
+
+        // Tampered or truncated proofs fail
+        let mut tampered = proofs[2].clone();
+        tampered[0][0] ^= 1;
+        assert!(!verify_rarity_proof(&root, &mints[2], multipliers[2], &tampered));
+        assert!(!verify_rarity_proof(&root, &mints[2], multipliers[2], &proofs[2][1..]));
+
+        // An inner node can't be passed off as a leaf
+        assert!(!verify_rarity_proof(&root, &mints[0], multipliers[0], &[]));
+    }
+
+    #[test]
+    fn test_resolve_rarity_multiplier() {
+        let mint = Pubkey::new_unique();
+        let other = rarity_leaf(&Pubkey::new_unique(), 10_000);
+        let root = rarity_node(&rarity_leaf(&mint, 25_000), &other);
+        let proof = |multiplier_bps| RarityProof { multiplier_bps, proof: vec![other] };
+
+        assert_eq!(resolve_rarity_multiplier(&root, &mint, Some(&proof(25_000))).unwrap(), 25_000);
+        assert_eq!(resolve_rarity_multiplier(&root, &mint, None).unwrap(), 10_000);
+        assert!(resolve_rarity_multiplier(&root, &mint, Some(&proof(20_000))).is_err());
+
+        // Without a committed root every NFT boosts at 1x
+        let unset = resolve_rarity_multiplier(&[0; 32], &mint, Some(&proof(25_000)));
+        assert_eq!(unset.unwrap(), 10_000);
+
+        // Multipliers outside 1x..=cap are rejected even with a valid proof
+        let low_root = rarity_node(&rarity_leaf(&mint, 5_000), &other);
+        assert!(resolve_rarity_multiplier(&low_root, &mint, Some(&proof(5_000))).is_err());
+
+        assert_eq!(scale_apy(2950, 25_000).unwrap(), 7375);
+        assert_eq!(scale_apy(2950, 10_000).unwrap(), 2950);
+        assert!(scale_apy(50_000, 50_000).is_err());
+    }
+
+    fn nft_metadata(collection: Option<Collection>, creators: Option<Vec<Creator>>) -> Metadata {
+        Metadata {
+            key: Key::MetadataV1,
+            update_authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            name: String::new(),
+            symbol: String::new(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_nft_collection() {
+        let collection = Pubkey::new_unique();
+        let verify = |nft_collection| {
+            verify_nft_collection(&nft_metadata(Some(nft_collection), None), collection, &[])
+        };
+
+        assert!(verify(Collection { verified: true, key: collection }).is_ok());
+
+        // Claiming the collection without verification is not enough
+        assert!(verify(Collection { verified: false, key: collection }).is_err());
+        assert!(verify(Collection { verified: true, key: Pubkey::new_unique() }).is_err());
+        assert!(verify_nft_collection(&nft_metadata(None, None), collection, &[]).is_err());
+    }
+
+    #[test]
+    fn test_verify_nft_collection_by_allowed_creator() {
+        let collection = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let verify = |verified, allowed_creators: &[Pubkey]| {
+            let creators = vec![Creator { address: creator, verified, share: 100 }];
+            verify_nft_collection(&nft_metadata(None, Some(creators)), collection, allowed_creators)
+        };
+
+        assert!(verify(true, &[creator]).is_ok());
+        assert!(verify(false, &[creator]).is_err());
+        assert!(verify(true, &[]).is_err());
+    }
+
+    #[test]
+    fn test_is_programmable_nft() {
+        let with_standard = |token_standard| Metadata {
+            token_standard,
+            ..nft_metadata(None, None)
+        };
+
+        assert!(is_programmable_nft(&with_standard(Some(TokenStandard::ProgrammableNonFungible))));
+        assert!(
+            is_programmable_nft(
+                &with_standard(Some(TokenStandard::ProgrammableNonFungibleEdition))
+            )
+        );
+        assert!(!is_programmable_nft(&with_standard(Some(TokenStandard::NonFungible))));
+        assert!(!is_programmable_nft(&with_standard(None)));
+    }
+
+    fn legacy_account_data<T: Discriminator>(
+        account: &impl AnchorSerialize,
+        len: usize
+    ) -> Vec<u8> {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.extend(account.try_to_vec().unwrap());
+        data.resize(len, 0);
+        data
+    }
+
+    // The baseline config was written field by field into `Config::len()` = 98 bytes
+    fn baseline_config(admin: Pubkey) -> Vec<u8> {
+        let mut data = Config::DISCRIMINATOR.to_vec();
+        data.extend(admin.as_ref());
+        data.extend(Pubkey::new_unique().as_ref());
+        (750_000u64).serialize(&mut data).unwrap();
+        (90u16).serialize(&mut data).unwrap();
+        (14u16).serialize(&mut data).unwrap();
+        (550u16).serialize(&mut data).unwrap();
+        for (days, apy) in [(30u16, 2950u16), (60, 5950), (90, 10450)] {
+            days.serialize(&mut data).unwrap();
+            apy.serialize(&mut data).unwrap();
+        }
+        assert_eq!(data.len(), 98);
+        data
+    }
+
+    fn test_config() -> Config {
+        let data = baseline_config(Pubkey::new_unique());
+        let legacy: ConfigV0 = read_legacy_account::<Config, _>(&data, true).unwrap();
+
+        let mut config = legacy.into_config(Pubkey::new_unique(), 9, 0);
+        config.base_apy_tiers = vec![BaseApyTier { days: 30, apy: 800 }];
+        config.amount_brackets = vec![AmountBracket { min_amount: 1_000, bonus_apy: 50 }];
+        config
+    }
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let admin = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let data = baseline_config(admin);
+        assert_eq!(data.len(), 8 + ConfigV0::INIT_SPACE);
+
+        let read: ConfigV0 = read_legacy_account::<Config, _>(&data, true).unwrap();
+        let config = read.into_config(mint, 6, 2);
+
+        assert_eq!(config.version, 1);
+        assert_eq!(config.admin, admin);
+        assert_eq!(config.nft_collection.as_ref(), &data[40..72]);
+        assert_eq!(config.max_nft_reward_lamports, 750_000);
+        assert_eq!(config.max_nft_apy_duration_days, 90);
+        assert_eq!(config.base_lock_days, 14);
+        assert_eq!(config.base_apy, 550);
+        let tiers: Vec<(u16, u16, bool)> = config.nft_days_apy
+            .iter()
+            .map(|tier| (tier.days, tier.apy, tier.enabled))
+            .collect();
+        assert_eq!(tiers, [(30, 2950, true), (60, 5950, true), (90, 10450, true)]);
+
+        // Every role falls to the admin
+        assert_eq!(config.pending_admin, Pubkey::default());
+        assert_eq!(config.config_manager, admin);
+        assert_eq!(config.treasury_manager, admin);
+        assert_eq!(config.pauser, admin);
+
+        // The vault mint was both staked and paid out 1:1
+        assert_eq!(config.mint, mint);
+        assert_eq!(config.reward_mint, mint);
+        assert_eq!(config.mint_decimals, 6);
+        assert_eq!(config.reward_mint_decimals, 6);
+        assert_eq!(config.reward_rate, default_reward_rate(6, 6).unwrap());
+        assert!(validate_config(&config).is_ok());
+
+        // Baseline stake infos are awaited before the liability is complete
+        assert_eq!(config.legacy_stake_infos, 2);
+
+        // The migrated account fits its allocation and reads back with the current layout
+        let mut migrated = vec![0; 8 + Config::INIT_SPACE];
+        config.try_serialize(&mut &mut migrated[..]).unwrap();
+        let config = Config::try_deserialize(&mut &migrated[..]).unwrap();
+        assert_eq!(config.admin, admin);
+        assert_eq!(config.reward_mint, mint);
+
+        // Migrated configs are no longer the baseline length
+        assert_ne!(migrated.len(), 8 + ConfigV0::INIT_SPACE);
+    }
+
+    // Baseline stake entries are written field by field, so the fixtures don't depend on the
+    // legacy layouts they are read with
+    fn baseline_stake_entry(
+        amount: u64,
+        nft: Option<(Pubkey, u16, u16)>,
+        destake_time: Option<i64>,
+        parent_stake_index: Option<u64>
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        amount.serialize(&mut data).unwrap();
+        (1_700_000_000i64).serialize(&mut data).unwrap();
+        (14u16).serialize(&mut data).unwrap();
+        (550u16).serialize(&mut data).unwrap();
+        nft.map(|(nft, _, _)| nft).serialize(&mut data).unwrap();
+        nft.map(|_| 1_700_000_000i64).serialize(&mut data).unwrap();
+        nft.map(|(_, lock_days, _)| lock_days).serialize(&mut data).unwrap();
+        nft.map(|(_, _, apy)| apy).serialize(&mut data).unwrap();
+        None::<i64>.serialize(&mut data).unwrap();
+        destake_time.serialize(&mut data).unwrap();
+        destake_time.filter(|_| nft.is_some()).serialize(&mut data).unwrap();
+        parent_stake_index.serialize(&mut data).unwrap();
+        (12u64).serialize(&mut data).unwrap();
+        (750_000u64).serialize(&mut data).unwrap();
+        (90u16).serialize(&mut data).unwrap();
+        data
+    }
+
+    fn baseline_stake_info(owner: Pubkey, stakes: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = StakeInfo::DISCRIMINATOR.to_vec();
+        data.extend(owner.as_ref());
+        (stakes.len() as u32).serialize(&mut data).unwrap();
+        stakes.iter().for_each(|stake_entry| data.extend(stake_entry));
+
+        // Stake infos were grown by the in-memory size of an entry, leaving trailing zeros
+        data.resize(8 + 32 + 4 + stakes.len() * 176, 0);
+        data
+    }
+
+    #[test]
+    fn test_migrate_legacy_stake_info() {
+        let owner = Pubkey::new_unique();
+        let nft = Pubkey::new_unique();
+        let data = baseline_stake_info(owner, &[
+            baseline_stake_entry(100_000_000, None, None, None),
+            baseline_stake_entry(200_000_000, Some((nft, 30, 2950)), Some(1_700_864_000), None),
+            baseline_stake_entry(200_000_000, Some((nft, 30, 2950)), None, Some(1)),
+        ]);
+
+        let read: StakeInfoV0 = read_legacy_account::<StakeInfo, _>(&data, true).unwrap();
+        let stake_info = StakeInfo::from(read);
+
+        assert_eq!(stake_info.version, 1);
+        assert_eq!(stake_info.address, owner);
+        assert_eq!(stake_info.next_stake_id, 3);
+        assert_eq!(stake_info.stake_count, 3);
+
+        // Entries keep their position as id and restakes point at their parent by id
+        let ids: Vec<u64> = stake_info.stakes.iter().map(|stake_entry| stake_entry.id).collect();
+        assert_eq!(ids, [0, 1, 2]);
+        assert_eq!(stake_info.find_stake_position(2).unwrap(), 2);
+        assert_eq!(stake_info.stakes[2].parent_stake_id, Some(1));
+
+        let restaked = &stake_info.stakes[1];
+        assert_eq!(restaked.amount, 200_000_000);
+        assert_eq!(restaked.destake_time, Some(1_700_864_000));
+        assert_eq!(restaked.paid_amount, 12);
+        assert_eq!(restaked.nfts.len(), 1);
+        assert_eq!(restaked.nfts[0].nft, nft);
+        assert_eq!(restaked.nfts[0].lock_days, 30);
+        assert_eq!(restaked.nfts[0].max_reward_lamports, 750_000);
+        assert!(stake_info.stakes[0].nfts.is_empty());
+
+        // Rewards were paid 1:1 in the stake mint
+        assert!(stake_info.stakes.iter().all(|entry| entry.reward_rate == 1_000_000_000));
+        let year_later = 1_700_000_000 + 365 * 86_400;
+        assert_eq!(
+            calculate_claimable_reward(&stake_info.stakes[0], year_later).unwrap(),
+            calculate_reward(100_000_000, 550, 365).unwrap() - 12
+        );
+
+        // The current layout starts with the version byte instead of the owner
+        let mut migrated = Vec::new();
+        stake_info.try_serialize(&mut migrated).unwrap();
+        assert_ne!(&migrated[8..40], owner.as_ref());
+        assert!(migrated.len() <= 8 + StakeInfo::INIT_SPACE + 3 * StakeEntry::INIT_SPACE);
+
+        let stake_info = StakeInfo::try_deserialize(&mut &migrated[..]).unwrap();
+        assert_eq!(stake_info.stakes[2].nfts[0].apy, 2950);
+    }
+
+    #[test]
+    fn test_shrunk_legacy_stake_info_still_migrates() {
+        let owner = Pubkey::new_unique();
+        let data = baseline_stake_info(owner, &[
+            baseline_stake_entry(100_000_000, None, None, None),
+            baseline_stake_entry(200_000_000, None, Some(1_700_864_000), None),
+        ]);
+
+        // Only the space the entries serialize to is kept
+        let legacy: StakeInfoV0 = read_legacy_account::<StakeInfo, _>(&data, true).unwrap();
+        let space = 8 + legacy.try_to_vec().unwrap().len();
+        assert!(space < data.len());
+
+        let shrunk: StakeInfoV0 = read_legacy_account::<StakeInfo, _>(
+            &data[..space],
+            true
+        ).unwrap();
+        let stake_info = StakeInfo::from(shrunk);
+        assert_eq!(stake_info.address, owner);
+        assert_eq!(stake_info.next_stake_id, 2);
+        assert_eq!(stake_info.stakes[1].amount, 200_000_000);
+        assert_eq!(stake_info.stakes[1].destake_time, Some(1_700_864_000));
+    }
+
+    #[test]
+    fn test_vault_withdrawal_waits_for_legacy_stake_infos() {
+        let data = baseline_config(Pubkey::new_unique());
+        let legacy: ConfigV0 = read_legacy_account::<Config, _>(&data, true).unwrap();
+        let mut config = legacy.into_config(Pubkey::new_unique(), 9, 1);
+
+        // The baseline stakes are owed rewards the liability does not hold yet
+        assert_eq!(config.reward_liability_at(0).unwrap(), 0);
+        assert!(check_vault_withdrawal(&config, 1_000_000_000, 1, 0).is_err());
+
+        let data = baseline_stake_info(Pubkey::new_unique(), &[
+            baseline_stake_entry(100_000_000_000, None, None, None),
+            baseline_stake_entry(100_000_000_000, None, Some(1_700_864_000), None),
+        ]);
+        let mut stake_info = StakeInfo::from(
+            read_legacy_account::<StakeInfo, StakeInfoV0>(&data, true).unwrap()
+        );
+        let current_time = 1_700_000_000 + 86_400;
+        count_legacy_stakes(&mut config, &mut stake_info, current_time).unwrap();
+
+        // Only the active stake is counted
+        assert_eq!(config.legacy_stake_infos, 0);
+        assert_eq!(config.total_staked, 100_000_000_000);
+        let liability = config.reward_liability_at(current_time).unwrap();
+        assert!(liability > 0);
+        assert_eq!(liability, stake_info.stakes[0].reward_liability);
+
+        // Once migrated, only the surplus over their rewards can be withdrawn
+        let vault_balance = liability + 1_000;
+        assert!(check_vault_withdrawal(&config, vault_balance, 1_000, current_time).is_ok());
+        assert!(check_vault_withdrawal(&config, vault_balance, 1_001, current_time).is_err());
+    }
+
+    #[test]
+    fn test_migrate_legacy_nft_info() {
+        let mut data = NftInfo::DISCRIMINATOR.to_vec();
+        (45u16).serialize(&mut data).unwrap();
+
+        let nft_info = NftInfo::from(
+            read_legacy_account::<NftInfo, NftInfoV0>(&data, true).unwrap()
+        );
+        assert_eq!(nft_info.version, 1);
+        assert_eq!(nft_info.days_locked, 45);
+    }
+
+    #[test]
+    fn test_read_legacy_account_rejects_other_accounts() {
+        let data = legacy_account_data::<NftInfo>(&(NftInfoV0 { days_locked: 45 }), 10);
+
+        assert!(read_legacy_account::<NftInfo, NftInfoV0>(&data, false).is_err());
+        assert!(read_legacy_account::<StakeEntry, NftInfoV0>(&data, true).is_err());
+        assert!(read_legacy_account::<NftInfo, NftInfoV0>(&data[..4], true).is_err());
+    }
 }
@@ -1,30 +1,257 @@
-use anchor_lang::solana_program::clock::SECONDS_PER_DAY;
+use anchor_lang::solana_program::{ clock::SECONDS_PER_DAY, hash::hashv };
 use anchor_lang::{ prelude::*, system_program };
 use rust_decimal::prelude::*;
-use crate::state::{ NftApy, StakeEntry };
-use crate::{ constants::APY_DECIMALS, error::ErrorCode };
+use crate::state::{
+    AmountBracket,
+    BaseApyTier,
+    Config,
+    CreatorAllowList,
+    LockedNft,
+    NftApy,
+    NftCollection,
+    RarityProof,
+    StakeEntry,
+    StakeInfo,
+};
+use crate::{ constants::*, error::ErrorCode };
+use anchor_spl::metadata::mpl_token_metadata::{
+    accounts::Metadata,
+    instructions::TransferCpiBuilder,
+    types::{ TokenStandard, TransferArgs },
+};
 use anchor_spl::token::{ transfer, Transfer };
 
 pub fn calculate_claimable_reward(stake_entry: &StakeEntry, current_time: i64) -> Result<u64> {
-    let StakeEntry { amount, base_apy, start_time, nft_lock_time, nft_apy, paid_amount, .. } =
-        *stake_entry;
+    let total_reward = calculate_total_reward(stake_entry, current_time)?;
+
+    Ok(total_reward.saturating_sub(stake_entry.paid_amount))
+}
+
+pub fn calculate_total_reward(stake_entry: &StakeEntry, current_time: i64) -> Result<u64> {
+    let StakeEntry { amount, base_apy, start_time, .. } = *stake_entry;
 
     let base_days = calculate_days_passed(start_time, current_time);
     let mut total_reward = calculate_reward(amount, base_apy, base_days as u64).ok_or(
         ErrorCode::RewardCalculationFailed
     )?;
 
-    if let (Some(nft_lock_time), Some(nft_apy)) = (nft_lock_time, nft_apy) {
-        let nft_days = calculate_days_passed(nft_lock_time, current_time);
-        let nft_effective_days = nft_days.min(stake_entry.max_nft_apy_duration_days as i64);
-        let nft_reward = calculate_reward(amount, nft_apy, nft_effective_days as u64)
-            .ok_or(ErrorCode::RewardCalculationFailed)?
-            .min(stake_entry.max_nft_reward_lamports);
+    for locked_nft in stake_entry.nfts.iter() {
+        let nft_days = calculate_days_passed(locked_nft.lock_time, current_time);
+        let nft_reward = calculate_nft_reward(amount, locked_nft, nft_days as u64)?;
 
         total_reward = total_reward.saturating_add(nft_reward);
     }
 
-    Ok(total_reward.saturating_sub(paid_amount))
+    convert_to_reward(total_reward, stake_entry.reward_rate).ok_or(
+        ErrorCode::RewardCalculationFailed.into()
+    )
+}
+
+pub fn restart_lock(stake_entry: &mut StakeEntry, current_time: i64) -> Result<()> {
+    require!(
+        calculate_claimable_reward(stake_entry, current_time)? == 0,
+        ErrorCode::UnsettledReward
+    );
+
+    stake_entry.start_time = current_time;
+    stake_entry.paid_amount = calculate_total_reward(stake_entry, current_time)?;
+
+    Ok(())
+}
+
+pub fn remove_principal(
+    stake_entry: &mut StakeEntry,
+    amount: u64,
+    amount_brackets: &[AmountBracket],
+    current_time: i64
+) -> Result<()> {
+    require!(amount < stake_entry.amount, ErrorCode::InvalidPartialAmount);
+
+    let claimable_reward = calculate_claimable_reward(stake_entry, current_time)?;
+
+    // The NFT reward caps shrink with the principal so the remainder keeps the
+    // same boost it would have had as a separate stake.
+    let remaining_amount = stake_entry.amount - amount;
+    let scale_cap = |cap: u64| {
+        (cap as u128)
+            .checked_mul(remaining_amount as u128)
+            .and_then(|cap| cap.checked_div(stake_entry.amount as u128))
+            .and_then(|cap| u64::try_from(cap).ok())
+            .ok_or(ErrorCode::MathOverflow)
+    };
+
+    stake_entry.max_nft_reward_lamports = scale_cap(stake_entry.max_nft_reward_lamports)?;
+    for locked_nft in stake_entry.nfts.iter_mut() {
+        locked_nft.max_reward_lamports = scale_cap(locked_nft.max_reward_lamports)?;
+    }
+    stake_entry.amount = remaining_amount;
+
+    // The amount bonus drops to the bracket of the remainder, and never rises above the
+    // bonus the stake was opened with
+    let amount_bonus_apy = get_amount_bonus_apy(remaining_amount, amount_brackets).min(
+        stake_entry.amount_bonus_apy
+    );
+    stake_entry.base_apy = stake_entry.base_apy
+        .saturating_sub(stake_entry.amount_bonus_apy)
+        .saturating_add(amount_bonus_apy);
+    stake_entry.amount_bonus_apy = amount_bonus_apy;
+
+    let total_reward = calculate_total_reward(stake_entry, current_time)?;
+    stake_entry.paid_amount = total_reward.saturating_sub(claimable_reward);
+
+    Ok(())
+}
+
+pub fn add_principal(stake_entry: &mut StakeEntry, amount: u64, current_time: i64) -> Result<()> {
+    let reward_before = calculate_total_reward(stake_entry, current_time)?;
+
+    stake_entry.amount = stake_entry.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+
+    let reward_after = calculate_total_reward(stake_entry, current_time)?;
+
+    // The added principal only earns from now on, so the reward it would have
+    // accrued since the start is settled as already paid.
+    stake_entry.add_payment(reward_after.saturating_sub(reward_before));
+
+    Ok(())
+}
+
+/// Reward the stake is committed to at `current_time`: the base reward through the end of the
+/// lock or of the current day, whichever is later, and every NFT reward through its max APY
+/// duration. Base rewards keep accruing after that, at `calculate_annual_base_reward`.
+pub fn calculate_committed_reward(stake_entry: &StakeEntry, current_time: i64) -> Result<u64> {
+    let StakeEntry { amount, base_apy, start_time, stake_lock_days, .. } = *stake_entry;
+
+    let base_days = (calculate_days_passed(start_time, current_time) as u64)
+        .saturating_add(1)
+        .max(stake_lock_days as u64);
+    let mut committed_reward = calculate_reward(amount, base_apy, base_days).ok_or(
+        ErrorCode::RewardCalculationFailed
+    )?;
+
+    for locked_nft in stake_entry.nfts.iter() {
+        let nft_days = locked_nft.max_apy_duration_days as u64;
+        let nft_reward = calculate_nft_reward(amount, locked_nft, nft_days)?;
+
+        committed_reward = committed_reward.saturating_add(nft_reward);
+    }
+
+    convert_to_reward(committed_reward, stake_entry.reward_rate).ok_or(
+        ErrorCode::RewardCalculationFailed.into()
+    )
+}
+
+pub fn calculate_annual_base_reward(stake_entry: &StakeEntry) -> Result<u64> {
+    let StakeEntry { amount, base_apy, reward_rate, .. } = *stake_entry;
+
+    calculate_reward(amount, base_apy, DAYS_PER_YEAR)
+        .and_then(|base_reward| convert_to_reward(base_reward, reward_rate))
+        .ok_or(ErrorCode::RewardCalculationFailed.into())
+}
+
+pub fn calculate_nft_reward(amount: u64, locked_nft: &LockedNft, nft_days: u64) -> Result<u64> {
+    let nft_effective_days = nft_days.min(locked_nft.max_apy_duration_days as u64);
+
+    calculate_reward(amount, locked_nft.apy, nft_effective_days)
+        .map(|nft_reward| nft_reward.min(locked_nft.max_reward_lamports))
+        .ok_or(ErrorCode::RewardCalculationFailed.into())
+}
+
+pub fn calculate_outstanding_reward(stake_entry: &StakeEntry, current_time: i64) -> Result<u64> {
+    if stake_entry.destake_time.is_some() {
+        return Ok(0);
+    }
+
+    Ok(
+        calculate_committed_reward(stake_entry, current_time)?.saturating_sub(
+            stake_entry.paid_amount
+        )
+    )
+}
+
+/// Takes the stake's share out of the pool liability, before the stake changes.
+pub fn release_reward_liability(
+    config: &mut Config,
+    stake_entry: &mut StakeEntry,
+    current_time: i64
+) -> Result<()> {
+    config.release_reward_liability(
+        stake_entry.reward_liability_at(current_time)?,
+        stake_entry.reward_accrual_per_year,
+        current_time
+    )?;
+
+    stake_entry.reward_liability = 0;
+    stake_entry.reward_accrual_per_year = 0;
+    stake_entry.liability_updated_at = current_time;
+
+    Ok(())
+}
+
+/// Adds the stake's share to the pool liability, after the stake changed. The share is what
+/// is outstanding now plus the base reward accruing from now on, which also counts the rest
+/// of the lock a second time, so the liability errs on the side of the vault.
+pub fn commit_reward_liability(
+    config: &mut Config,
+    stake_entry: &mut StakeEntry,
+    current_time: i64
+) -> Result<()> {
+    if stake_entry.destake_time.is_some() {
+        return Ok(());
+    }
+
+    stake_entry.reward_liability = calculate_outstanding_reward(stake_entry, current_time)?;
+    stake_entry.reward_accrual_per_year = calculate_annual_base_reward(stake_entry)?;
+    stake_entry.liability_updated_at = current_time;
+
+    config.commit_reward_liability(
+        stake_entry.reward_liability,
+        stake_entry.reward_accrual_per_year,
+        current_time
+    )
+}
+
+/// Fails unless `amount` can leave the vault without dropping it below `reward_liability`.
+pub fn check_vault_surplus(vault_balance: u64, amount: u64, reward_liability: u64) -> Result<()> {
+    let remaining_balance = vault_balance
+        .checked_sub(amount)
+        .ok_or(ErrorCode::WithdrawExceedsSurplus)?;
+    require!(remaining_balance >= reward_liability, ErrorCode::WithdrawExceedsSurplus);
+
+    Ok(())
+}
+
+/// Fails unless `amount` can leave the vault without dropping it below the pool liability.
+/// Stakes of baseline stake infos only count towards it once migrated, so nothing can be
+/// withdrawn before then.
+pub fn check_vault_withdrawal(
+    config: &Config,
+    vault_balance: u64,
+    amount: u64,
+    current_time: i64
+) -> Result<()> {
+    require!(config.legacy_stake_infos == 0, ErrorCode::LegacyStakesPending);
+
+    check_vault_surplus(vault_balance, amount, config.reward_liability_at(current_time)?)
+}
+
+/// Counts the active stakes of a migrated baseline stake info towards the pool totals, which
+/// never included them.
+pub fn count_legacy_stakes(
+    config: &mut Config,
+    stake_info: &mut StakeInfo,
+    current_time: i64
+) -> Result<()> {
+    for stake_entry in stake_info.stakes.iter_mut() {
+        if stake_entry.destake_time.is_none() {
+            config.add_stake(stake_entry.amount)?;
+            commit_reward_liability(config, stake_entry, current_time)?;
+        }
+    }
+
+    config.legacy_stake_infos = config.legacy_stake_infos.saturating_sub(1);
+
+    Ok(())
 }
 
 pub fn calculate_reward(amount: u64, apy: u16, days_passed: u64) -> Option<u64> {
@@ -40,18 +267,59 @@ pub fn calculate_reward(amount: u64, apy: u16, days_passed: u64) -> Option<u64>
 
     let reward = d_amount.checked_mul(daily_multiplier)?.checked_mul(d_days_passed)?;
 
-    let result = reward.to_u64();
+    reward.to_u64()
+}
+
+pub fn calculate_penalty(amount: u64, penalty_bps: u16) -> Option<u64> {
+    let penalty = (amount as u128)
+        .checked_mul(penalty_bps as u128)?
+        .checked_div(MAX_BPS as u128)?;
 
-    result
+    u64::try_from(penalty).ok()
+}
+
+pub fn convert_to_reward(amount: u64, reward_rate: u64) -> Option<u64> {
+    let reward = (amount as u128)
+        .checked_mul(reward_rate as u128)?
+        .checked_div((10u128).pow(REWARD_RATE_DECIMALS as u32))?;
+
+    u64::try_from(reward).ok()
+}
+
+pub fn default_reward_rate(stake_decimals: u8, reward_decimals: u8) -> Result<u64> {
+    let exponent = REWARD_RATE_DECIMALS.checked_add(reward_decimals)
+        .and_then(|exponent| exponent.checked_sub(stake_decimals))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    (10u64).checked_pow(u32::from(exponent)).ok_or(ErrorCode::MathOverflow.into())
+}
+
+pub fn check_lock_periods_ended(stake_entry: &StakeEntry, current_time: i64) -> Result<()> {
+    let base_days_passed = calculate_days_passed(stake_entry.start_time, current_time);
+    require!(
+        base_days_passed >= (stake_entry.stake_lock_days as i64),
+        ErrorCode::BaseLockPeriodNotEnded
+    );
+
+    for locked_nft in stake_entry.nfts.iter() {
+        let nft_days_passed = calculate_days_passed(locked_nft.lock_time, current_time);
+
+        require!(
+            nft_days_passed >= (locked_nft.lock_days as i64),
+            ErrorCode::NftLockPeriodNotEnded
+        );
+    }
+
+    Ok(())
 }
 
 pub fn calculate_days_passed(start_time: i64, current_time: i64) -> i64 {
     current_time.saturating_sub(start_time).max(0) / (SECONDS_PER_DAY as i64)
 }
 
-pub fn get_apy(lock_days: u16, nft_days_apy: [NftApy; 3]) -> Result<u16> {
+pub fn get_apy(lock_days: u16, nft_days_apy: &[NftApy]) -> Result<u16> {
     for nft_apy in nft_days_apy.iter() {
-        if lock_days == nft_apy.days {
+        if lock_days == nft_apy.days && nft_apy.enabled {
             return Ok(nft_apy.apy);
         }
     }
@@ -59,6 +327,309 @@ pub fn get_apy(lock_days: u16, nft_days_apy: [NftApy; 3]) -> Result<u16> {
     Err(ErrorCode::InvalidStakePeriod.into())
 }
 
+pub fn get_base_apy(
+    lock_days: u16,
+    default_lock_days: u16,
+    default_apy: u16,
+    base_apy_tiers: &[BaseApyTier]
+) -> Result<u16> {
+    if lock_days == default_lock_days {
+        return Ok(default_apy);
+    }
+
+    base_apy_tiers
+        .iter()
+        .find(|tier| tier.days == lock_days)
+        .map(|tier| tier.apy)
+        .ok_or(ErrorCode::InvalidStakePeriod.into())
+}
+
+pub fn get_amount_bonus_apy(amount: u64, amount_brackets: &[AmountBracket]) -> u16 {
+    amount_brackets
+        .iter()
+        .rev()
+        .find(|bracket| amount >= bracket.min_amount)
+        .map_or(0, |bracket| bracket.bonus_apy)
+}
+
+pub fn validate_config(config: &Config) -> Result<()> {
+    require!(
+        config.base_apy > 0 && config.base_apy <= MAX_BASE_APY,
+        ErrorCode::InvalidBaseApy
+    );
+    require!(config.base_lock_days > 0, ErrorCode::InvalidBaseLockDays);
+    require!(config.reward_rate > 0, ErrorCode::InvalidRewardRate);
+    require!(config.early_destake_penalty_bps <= MAX_BPS, ErrorCode::InvalidPenaltyBps);
+    require!(config.config_change_delay >= 0, ErrorCode::InvalidConfigChangeDelay);
+
+    validate_nft_schedule(&config.nft_days_apy, config.max_nft_apy_duration_days)?;
+    validate_base_apy_tiers(&config.base_apy_tiers)?;
+    validate_amount_brackets(&config.amount_brackets)?;
+    validate_nft_stack_multipliers(&config.nft_stack_multipliers_bps)?;
+
+    // `get_base_apy` resolves the base lock days to `base_apy` before looking at the tiers
+    require!(
+        config.base_apy_tiers.iter().all(|tier| tier.days != config.base_lock_days),
+        ErrorCode::BaseApyTierShadowed
+    );
+
+    // Penalties are taken in the stake mint, so they can only go to the vault when it holds it
+    require!(
+        config.early_destake_penalty_bps == 0 ||
+            config.penalty_treasury != Pubkey::default() ||
+            config.mint == config.reward_mint,
+        ErrorCode::PenaltyTreasuryRequired
+    );
+
+    Ok(())
+}
+
+pub fn validate_nft_schedule(
+    nft_days_apy: &[NftApy],
+    max_nft_apy_duration_days: u16
+) -> Result<()> {
+    require!(max_nft_apy_duration_days > 0, ErrorCode::InvalidMaxNftApyDuration);
+
+    validate_nft_tiers(nft_days_apy)?;
+
+    // Tiers longer than the max duration could never be locked, see `NftInfo::can_lock`
+    require!(
+        nft_days_apy.iter().all(|tier| tier.days <= max_nft_apy_duration_days),
+        ErrorCode::NftTierExceedsMaxDuration
+    );
+
+    Ok(())
+}
+
+pub fn validate_nft_stack_multipliers(nft_stack_multipliers_bps: &[u16]) -> Result<()> {
+    require!(
+        nft_stack_multipliers_bps.len() <= MAX_NFTS_PER_STAKE &&
+            nft_stack_multipliers_bps
+                .iter()
+                .all(|multiplier_bps| (1..=MAX_BPS).contains(multiplier_bps)) &&
+            nft_stack_multipliers_bps.windows(2).all(|pair| pair[0] >= pair[1]),
+        ErrorCode::InvalidNftStackMultipliers
+    );
+
+    Ok(())
+}
+
+pub fn validate_base_apy_tiers(base_apy_tiers: &[BaseApyTier]) -> Result<()> {
+    require!(base_apy_tiers.len() <= MAX_BASE_APY_TIERS, ErrorCode::TooManyBaseApyTiers);
+    require!(
+        base_apy_tiers.iter().all(|tier| tier.days > 0 && tier.apy > 0) &&
+            base_apy_tiers.windows(2).all(|pair| pair[0].days < pair[1].days),
+        ErrorCode::InvalidBaseApyTiers
+    );
+    require!(
+        base_apy_tiers.iter().all(|tier| tier.apy <= MAX_BASE_APY),
+        ErrorCode::BaseApyTierTooHigh
+    );
+
+    Ok(())
+}
+
+pub fn validate_amount_brackets(amount_brackets: &[AmountBracket]) -> Result<()> {
+    require!(amount_brackets.len() <= MAX_AMOUNT_BRACKETS, ErrorCode::TooManyAmountBrackets);
+    require!(
+        amount_brackets.iter().all(|bracket| bracket.bonus_apy > 0) &&
+            amount_brackets.windows(2).all(|pair| pair[0].min_amount < pair[1].min_amount),
+        ErrorCode::InvalidAmountBrackets
+    );
+    require!(
+        amount_brackets.iter().all(|bracket| bracket.bonus_apy <= MAX_AMOUNT_BONUS_APY),
+        ErrorCode::AmountBonusApyTooHigh
+    );
+
+    Ok(())
+}
+
+pub fn validate_nft_tiers(nft_days_apy: &[NftApy]) -> Result<()> {
+    require!(nft_days_apy.len() <= MAX_NFT_TIERS, ErrorCode::TooManyNftTiers);
+
+    for nft_apy in nft_days_apy.iter() {
+        require!(nft_apy.days > 0, ErrorCode::InvalidNftTierDays);
+        require!(nft_apy.apy > 0, ErrorCode::InvalidNftTierApy);
+        require!(nft_apy.apy <= MAX_NFT_APY, ErrorCode::NftTierApyTooHigh);
+    }
+
+    require!(
+        nft_days_apy
+            .iter()
+            .enumerate()
+            .all(|(i, tier)| nft_days_apy[..i].iter().all(|other| other.days != tier.days)),
+        ErrorCode::DuplicateNftTierDays
+    );
+    require!(
+        nft_days_apy.windows(2).all(|pair| pair[0].days < pair[1].days),
+        ErrorCode::NftTiersNotSorted
+    );
+
+    Ok(())
+}
+
+pub fn insert_nft_tier(nft_days_apy: &mut Vec<NftApy>, nft_apy: NftApy) -> Result<()> {
+    let position = match nft_days_apy.binary_search_by_key(&nft_apy.days, |tier| tier.days) {
+        Ok(_) => {
+            return Err(ErrorCode::NftTierAlreadyExists.into());
+        }
+        Err(position) => position,
+    };
+
+    nft_days_apy.insert(position, nft_apy);
+
+    validate_nft_tiers(nft_days_apy)
+}
+
+pub fn remove_nft_tier(nft_days_apy: &mut Vec<NftApy>, days: u16) -> Result<NftApy> {
+    let position = nft_days_apy
+        .iter()
+        .position(|tier| tier.days == days)
+        .ok_or(ErrorCode::NftTierNotFound)?;
+
+    Ok(nft_days_apy.remove(position))
+}
+
+pub fn set_nft_tier_enabled(nft_days_apy: &mut [NftApy], days: u16, enabled: bool) -> Result<()> {
+    let nft_apy = nft_days_apy
+        .iter_mut()
+        .find(|tier| tier.days == days)
+        .ok_or(ErrorCode::NftTierNotFound)?;
+
+    nft_apy.enabled = enabled;
+
+    Ok(())
+}
+
+/// Accepts NFTs from the verified `collection`, or failing that, NFTs carrying one of the
+/// `allowed_creators` as a verified creator.
+pub fn verify_nft_collection(
+    metadata: &Metadata,
+    collection: Pubkey,
+    allowed_creators: &[Pubkey]
+) -> Result<()> {
+    let in_collection = metadata.collection
+        .as_ref()
+        .is_some_and(|nft_collection| nft_collection.verified && nft_collection.key == collection);
+
+    let by_allowed_creator = metadata.creators
+        .iter()
+        .flatten()
+        .any(|creator| creator.verified && allowed_creators.contains(&creator.address));
+
+    require!(in_collection || by_allowed_creator, ErrorCode::InvalidCollection);
+
+    Ok(())
+}
+
+/// Checks the NFT against a registered partner collection when one is given, otherwise
+/// against the primary collection and its creator allow-list.
+pub fn verify_nft_eligibility(
+    metadata: &Metadata,
+    config: &Config,
+    nft_collection: Option<&NftCollection>,
+    creator_allow_list: Option<&CreatorAllowList>
+) -> Result<()> {
+    match nft_collection {
+        Some(nft_collection) => verify_nft_collection(metadata, nft_collection.collection, &[]),
+        None =>
+            verify_nft_collection(
+                metadata,
+                config.nft_collection,
+                creator_allow_list.map_or(&[], |allow_list| &allow_list.creators)
+            ),
+    }
+}
+
+/// Returns the NFT's rarity multiplier in basis points. Without a committed root or a
+/// proof the NFT boosts at 1x.
+pub fn resolve_rarity_multiplier(
+    rarity_root: &[u8; 32],
+    mint: &Pubkey,
+    rarity_proof: Option<&RarityProof>
+) -> Result<u16> {
+    let Some(rarity_proof) = rarity_proof.filter(|_| *rarity_root != [0; 32]) else {
+        return Ok(MAX_BPS);
+    };
+
+    require!(
+        (MAX_BPS..=MAX_RARITY_MULTIPLIER_BPS).contains(&rarity_proof.multiplier_bps),
+        ErrorCode::InvalidRarityMultiplier
+    );
+    require!(
+        verify_rarity_proof(rarity_root, mint, rarity_proof.multiplier_bps, &rarity_proof.proof),
+        ErrorCode::InvalidRarityProof
+    );
+
+    Ok(rarity_proof.multiplier_bps)
+}
+
+pub fn verify_rarity_proof(
+    rarity_root: &[u8; 32],
+    mint: &Pubkey,
+    multiplier_bps: u16,
+    proof: &[[u8; 32]]
+) -> bool {
+    let computed_root = proof
+        .iter()
+        .fold(rarity_leaf(mint, multiplier_bps), |node, sibling| rarity_node(&node, sibling));
+
+    computed_root == *rarity_root
+}
+
+// Leaves and nodes are hashed with distinct prefixes so a node can't be passed off as a leaf
+pub fn rarity_leaf(mint: &Pubkey, multiplier_bps: u16) -> [u8; 32] {
+    hashv(&[&[0], mint.as_ref(), &multiplier_bps.to_le_bytes()]).to_bytes()
+}
+
+// Pairs are hashed in sorted order so proofs don't need to carry left/right positions
+pub fn rarity_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+
+    hashv(&[&[1], left, right]).to_bytes()
+}
+
+pub fn scale_apy(apy: u16, multiplier_bps: u16) -> Result<u16> {
+    let scaled_apy = (apy as u32)
+        .checked_mul(multiplier_bps as u32)
+        .and_then(|apy| apy.checked_div(MAX_BPS as u32))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    u16::try_from(scaled_apy).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Scales the reward cap of the next NFT by its stack multiplier, so every NFT stacked on a
+/// stake does not earn up to the full cap.
+pub fn scale_nft_reward_cap(max_reward_lamports: u64, stack_multiplier_bps: u16) -> Result<u64> {
+    let scaled_cap = (max_reward_lamports as u128)
+        .checked_mul(stack_multiplier_bps as u128)
+        .and_then(|cap| cap.checked_div(MAX_BPS as u128))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    u64::try_from(scaled_cap).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Reduces the APY of the next NFT so the NFTs locked in the stake stay within
+/// `max_stacked_nft_apy`.
+pub fn cap_stacked_nft_apy(
+    apy: u16,
+    stake_entry: &StakeEntry,
+    max_stacked_nft_apy: u16
+) -> Result<u16> {
+    if max_stacked_nft_apy == 0 {
+        return Ok(apy);
+    }
+
+    let stacked_apy = stake_entry
+        .locked_nfts()
+        .fold(0u16, |stacked_apy, locked_nft| stacked_apy.saturating_add(locked_nft.apy));
+    let capped_apy = apy.min(max_stacked_nft_apy.saturating_sub(stacked_apy));
+
+    require!(capped_apy > 0, ErrorCode::NftStackCapReached);
+
+    Ok(capped_apy)
+}
+
 pub fn to_lamports(amount: u64, decimals: u8) -> Result<u64> {
     amount
         .checked_mul((10u64).checked_pow(u32::from(decimals)).ok_or(ErrorCode::MathOverflow)?)
@@ -88,21 +659,86 @@ pub fn transfer_tokens<'info>(
     transfer(cpi_context, amount)
 }
 
-pub fn resize_account<'info, T: AccountSerialize + AccountDeserialize + Owner + Clone>(
-    account: &mut Account<'info, T>,
-    payer: &Signer<'info>,
-    system_program: &Program<'info, System>,
-    additional_space: usize
+/// Accounts Token Metadata needs to move a programmable NFT between token accounts.
+pub struct ProgrammableNftTransfer<'info> {
+    pub token: AccountInfo<'info>,
+    pub token_owner: AccountInfo<'info>,
+    pub destination_token: AccountInfo<'info>,
+    pub destination_owner: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub token_record: AccountInfo<'info>,
+    pub destination_token_record: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub authorization_rules_program: Option<AccountInfo<'info>>,
+    pub authorization_rules: Option<AccountInfo<'info>>,
+}
+
+pub fn is_programmable_nft(metadata: &Metadata) -> bool {
+    matches!(
+        metadata.token_standard,
+        Some(TokenStandard::ProgrammableNonFungible | TokenStandard::ProgrammableNonFungibleEdition)
+    )
+}
+
+/// Unwraps an optional account that programmable NFT transfers cannot do without.
+pub fn programmable_nft_account<'info>(
+    account: Option<&UncheckedAccount<'info>>
+) -> Result<AccountInfo<'info>> {
+    account
+        .map(|account| account.to_account_info())
+        .ok_or(ErrorCode::MissingProgrammableNftAccounts.into())
+}
+
+/// Programmable NFTs sit frozen in their token accounts, so they are moved through Token
+/// Metadata, which thaws the source, checks the authorization rules and refreezes the
+/// destination.
+pub fn transfer_programmable_nft<'info>(
+    token_metadata_program: AccountInfo<'info>,
+    accounts: ProgrammableNftTransfer<'info>,
+    signer_seeds: Option<&[&[&[u8]]]>
 ) -> Result<()> {
-    let account_info = account.to_account_info();
-    let current_space = account_info.data_len();
-    let new_space = current_space
-        .checked_add(additional_space)
-        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    TransferCpiBuilder::new(&token_metadata_program)
+        .token(&accounts.token)
+        .token_owner(&accounts.token_owner)
+        .destination_token(&accounts.destination_token)
+        .destination_owner(&accounts.destination_owner)
+        .mint(&accounts.mint)
+        .metadata(&accounts.metadata)
+        .edition(Some(&accounts.edition))
+        .token_record(Some(&accounts.token_record))
+        .destination_token_record(Some(&accounts.destination_token_record))
+        .authority(&accounts.authority)
+        .payer(&accounts.payer)
+        .system_program(&accounts.system_program)
+        .sysvar_instructions(&accounts.sysvar_instructions)
+        .spl_token_program(&accounts.token_program)
+        .spl_ata_program(&accounts.associated_token_program)
+        .authorization_rules_program(accounts.authorization_rules_program.as_ref())
+        .authorization_rules(accounts.authorization_rules.as_ref())
+        .transfer_args(TransferArgs::V1 { amount: 1, authorization_data: None })
+        .invoke_signed(signer_seeds.unwrap_or_default())
+        .map_err(Into::into)
+}
 
+/// Reallocates the account to `space` and writes it, topping up the rent from the payer or
+/// refunding the surplus to them. Returns the refunded lamports.
+pub fn write_account<'info, T: AccountSerialize>(
+    account_info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    account: &T,
+    space: usize
+) -> Result<u64> {
     let rent = Rent::get()?;
-    let new_minimum_balance = rent.minimum_balance(new_space);
-    let lamports_diff = new_minimum_balance.saturating_sub(account_info.lamports());
+    let minimum_balance = rent.minimum_balance(space);
+    let lamports_diff = minimum_balance.saturating_sub(account_info.lamports());
 
     if lamports_diff > 0 {
         system_program::transfer(
@@ -114,7 +750,46 @@ pub fn resize_account<'info, T: AccountSerialize + AccountDeserialize + Owner +
         )?;
     }
 
+    account_info.realloc(space, true).map_err(|_| ErrorCode::ReallocError)?;
+    account.try_serialize(&mut &mut account_info.try_borrow_mut_data()?[..])?;
+
+    let refund = account_info.lamports().saturating_sub(minimum_balance);
+
+    if refund > 0 {
+        **account_info.try_borrow_mut_lamports()? -= refund;
+        **payer.to_account_info().try_borrow_mut_lamports()? += refund;
+    }
+
+    Ok(refund)
+}
+
+pub fn shrink_account<'info, T: AccountSerialize + AccountDeserialize + Owner + Clone>(
+    account: &mut Account<'info, T>,
+    receiver: &Signer<'info>
+) -> Result<u64> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data)?;
+
+    truncate_account(&account.to_account_info(), receiver, data.len())
+}
+
+/// Cuts the account data down to `new_space` bytes and refunds the rent no longer needed to
+/// the receiver. Returns the refunded lamports.
+pub fn truncate_account<'info>(
+    account_info: &AccountInfo<'info>,
+    receiver: &Signer<'info>,
+    new_space: usize
+) -> Result<u64> {
     account_info.realloc(new_space, false).map_err(|_| ErrorCode::ReallocError)?;
 
-    Ok(())
+    let rent = Rent::get()?;
+    let new_minimum_balance = rent.minimum_balance(new_space);
+    let refund = account_info.lamports().saturating_sub(new_minimum_balance);
+
+    if refund > 0 {
+        **account_info.try_borrow_mut_lamports()? -= refund;
+        **receiver.to_account_info().try_borrow_mut_lamports()? += refund;
+    }
+
+    Ok(refund)
 }
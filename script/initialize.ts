@@ -42,6 +42,7 @@ async function initialize() {
     accounts: {
       signer: singer.publicKey,
       mint: SPL_MINT,
+      rewardMint: SPL_MINT,
       nftCollection: COLLECTION_MINT,
     },
     program,
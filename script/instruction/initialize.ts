@@ -6,6 +6,7 @@ type InitializeProgramConfigAgrs = {
   accounts: {
     signer: PublicKey;
     mint: PublicKey;
+    rewardMint: PublicKey;
     nftCollection: PublicKey;
   };
   program: Program<ViridisStaking>;
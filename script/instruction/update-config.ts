@@ -4,7 +4,7 @@ import { ViridisStaking } from "../../target/types/viridis_staking";
 
 type InitializeProgramConfigAgrs = {
   accounts: {
-    admin: PublicKey;
+    configManager: PublicKey;
   };
   program: Program<ViridisStaking>;
 };
@@ -14,15 +14,14 @@ export function getUpdateConfigIx({
   program,
 }: InitializeProgramConfigAgrs) {
   const updateArgs = {
-    admin: null,
-    baseLockDays: 0,
+    baseLockDays: 1,
     maxNftApyDurationDays: null,
     baseApy: 350,
     maxNftRewardLamports: null,
     nftDaysApy: [
-      { days: 45, apy: 1650 },
-      { days: 80, apy: 4650 },
-      { days: 90, apy: 10650 },
+      { days: 45, apy: 1650, enabled: true },
+      { days: 80, apy: 4650, enabled: true },
+      { days: 90, apy: 10650, enabled: true },
     ],
   };
 
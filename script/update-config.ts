@@ -41,7 +41,7 @@ async function updateConfig() {
 
   const ix = await getUpdateConfigIx({
     accounts: {
-      admin: singer.publicKey,
+      configManager: singer.publicKey,
     },
     program,
   });
@@ -198,6 +198,7 @@ describe("staking program in the local node", () => {
         .initialize()
         .accounts({
           mint: mintKeypair.publicKey,
+          rewardMint: mintKeypair.publicKey,
           nftCollection: addresses.nftCollection,
         })
         .signers([userA])
@@ -214,8 +215,7 @@ describe("staking program in the local node", () => {
 
       await program.methods
         .updateConfig({
-          admin: null,
-          baseLockDays: new BN(0),
+          baseLockDays: new BN(1),
           baseApy: null,
           maxNftRewardLamports: null,
           maxNftApyDurationDays: null,
@@ -226,7 +226,7 @@ describe("staking program in the local node", () => {
       await program.methods.initializeStakeInfo().signers([userA]).rpc();
 
       await program.methods
-        .stake(new BN(dUserTokens / 4n))
+        .stake(new BN(dUserTokens / 4n), null)
         .accounts({
           mint: mintKeypair.publicKey,
         })
@@ -234,7 +234,7 @@ describe("staking program in the local node", () => {
         .rpc();
 
       await program.methods
-        .stake(new BN(dUserTokens / 4n))
+        .stake(new BN(dUserTokens / 4n), null)
         .accounts({
           mint: mintKeypair.publicKey,
         })
@@ -242,7 +242,7 @@ describe("staking program in the local node", () => {
         .rpc();
 
       await program.methods
-        .stake(new BN(dUserTokens / 4n))
+        .stake(new BN(dUserTokens / 4n), null)
         .accounts({
           mint: mintKeypair.publicKey,
         })
@@ -250,7 +250,7 @@ describe("staking program in the local node", () => {
         .rpc();
 
       await program.methods
-        .stake(new BN(dUserTokens / 4n))
+        .stake(new BN(dUserTokens / 4n), null)
         .accounts({
           mint: mintKeypair.publicKey,
         })
@@ -258,7 +258,7 @@ describe("staking program in the local node", () => {
         .rpc();
 
       await program.methods
-        .lockNft(new BN(0), new BN(30))
+        .lockNft(new BN(0), new BN(30), null)
         .accounts({
           mint: addresses.nft,
         })
@@ -268,7 +268,7 @@ describe("staking program in the local node", () => {
       await program.methods
         .claim(new BN(0))
         .accounts({
-          mint: mintKeypair.publicKey,
+          rewardMint: mintKeypair.publicKey,
         })
         .signers([userA])
         .rpc();
@@ -276,7 +276,7 @@ describe("staking program in the local node", () => {
       await program.methods
         .restake(new BN(0))
         .accounts({
-          mint: mintKeypair.publicKey,
+          rewardMint: mintKeypair.publicKey,
         })
         .signers([userA])
         .rpc();
@@ -285,6 +285,7 @@ describe("staking program in the local node", () => {
       //   .destake(new BN(0))
       //   .accounts({
       //     mint: mintKeypair.publicKey,
+      //     rewardMint: mintKeypair.publicKey,
       //   })
       //   .signers([payer])
       //   .rpc();
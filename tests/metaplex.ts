@@ -1,7 +1,27 @@
-import { AccountInfo, Connection, PublicKey } from "@solana/web3.js";
-import { deserializeMetadata } from "@metaplex-foundation/mpl-token-metadata";
-import { publicKey } from "@metaplex-foundation/umi";
+import { AccountInfo, Keypair, PublicKey } from "@solana/web3.js";
+import {
+  AccountState,
+  MINT_SIZE,
+  MintLayout,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import {
+  TokenStandard,
+  TokenState,
+  deserializeMetadata,
+  getMasterEditionAccountDataSerializer,
+  getMetadataAccountDataSerializer,
+  getTokenRecordAccountDataSerializer,
+} from "@metaplex-foundation/mpl-token-metadata";
+import { none, publicKey, some } from "@metaplex-foundation/umi";
+import { ProgramTestContext } from "solana-bankrun";
 import { TOKEN_METADATA_PROGRAM_ID } from "../const";
+import { setSplToAccount } from "./utils";
+
+const METADATA_SIZE = 679;
+const MASTER_EDITION_SIZE = 282;
+const TOKEN_RECORD_SIZE = 80;
 
 export function deserializeMetaplexMetadata(
   address: PublicKey,
@@ -55,3 +75,135 @@ export function getCollectionAddress(
 
   return new PublicKey(nftCollection.key);
 }
+
+export function getNftEditionAddress(mint: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from("edition"),
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+}
+
+export function getTokenRecordAddress(mint: PublicKey, token: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from("token_record"),
+      token.toBuffer(),
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+}
+
+export function deserializeTokenRecord(data: Uint8Array) {
+  return getTokenRecordAccountDataSerializer().deserialize(data)[0];
+}
+
+function setMetaplexAccount(
+  context: ProgramTestContext,
+  address: PublicKey,
+  data: Uint8Array,
+  size: number
+) {
+  context.setAccount(address, {
+    lamports: 1_000_000_000,
+    data: Buffer.concat([Buffer.from(data), Buffer.alloc(size - data.length)]),
+    owner: TOKEN_METADATA_PROGRAM_ID,
+    executable: false,
+  });
+}
+
+// Writes a programmable NFT of `collection` held by `owner` straight into the
+// bank: mint, metadata, master edition, the owner's frozen token account and
+// its token record.
+export function setProgrammableNft(
+  context: ProgramTestContext,
+  owner: PublicKey,
+  collection: PublicKey
+) {
+  const mint = Keypair.generate().publicKey;
+  const metadata = getNftMetadataAddress(mint);
+  const [edition, editionBump] = getNftEditionAddress(mint);
+  const ownerToken = getAssociatedTokenAddressSync(mint, owner);
+  const [ownerTokenRecord, tokenRecordBump] = getTokenRecordAddress(
+    mint,
+    ownerToken
+  );
+
+  const mintData = Buffer.alloc(MINT_SIZE);
+  MintLayout.encode(
+    {
+      mintAuthorityOption: 1,
+      mintAuthority: edition,
+      supply: 1n,
+      decimals: 0,
+      isInitialized: true,
+      freezeAuthorityOption: 1,
+      freezeAuthority: edition,
+    },
+    mintData
+  );
+  context.setAccount(mint, {
+    lamports: 1_000_000_000,
+    data: mintData,
+    owner: TOKEN_PROGRAM_ID,
+    executable: false,
+  });
+
+  setMetaplexAccount(
+    context,
+    metadata,
+    getMetadataAccountDataSerializer().serialize({
+      updateAuthority: publicKey(owner),
+      mint: publicKey(mint),
+      name: "Programmable NFT",
+      symbol: "PNFT",
+      uri: "",
+      sellerFeeBasisPoints: 0,
+      creators: none(),
+      primarySaleHappened: false,
+      isMutable: true,
+      editionNonce: some(editionBump),
+      tokenStandard: some(TokenStandard.ProgrammableNonFungible),
+      collection: some({ verified: true, key: publicKey(collection) }),
+      uses: none(),
+      collectionDetails: none(),
+      programmableConfig: some({ __kind: "V1", ruleSet: none() }),
+    }),
+    METADATA_SIZE
+  );
+
+  setMetaplexAccount(
+    context,
+    edition,
+    getMasterEditionAccountDataSerializer().serialize({
+      supply: 0,
+      maxSupply: some(0),
+    }),
+    MASTER_EDITION_SIZE
+  );
+
+  setSplToAccount(context, mint, owner, ownerToken, 1n, AccountState.Frozen);
+
+  setMetaplexAccount(
+    context,
+    ownerTokenRecord,
+    getTokenRecordAccountDataSerializer().serialize({
+      bump: tokenRecordBump,
+      state: TokenState.Unlocked,
+      ruleSetRevision: none(),
+      delegate: none(),
+      delegateRole: none(),
+      lockedTransfer: none(),
+    }),
+    TOKEN_RECORD_SIZE
+  );
+
+  return { mint, metadata, edition, ownerToken, ownerTokenRecord };
+}
@@ -7,10 +7,11 @@ export const stakeRpc = async (
   amountDecimals: bigint,
   signer: Keypair,
   mint: PublicKey,
-  program: Program<ViridisStaking>
+  program: Program<ViridisStaking>,
+  lockDays: number | null = null
 ) => {
   return program.methods
-    .stake(new BN(amountDecimals))
+    .stake(new BN(amountDecimals), lockDays)
     .accounts({
       signer: signer.publicKey,
       mint,
@@ -20,14 +21,14 @@ export const stakeRpc = async (
 };
 
 export const lockNftRpc = async (
-  stakeIndex: number,
+  stakeId: number,
   lockPeriod: number,
   signer: Keypair,
   mint: PublicKey,
   program: Program<ViridisStaking>
 ) => {
   return await program.methods
-    .lockNft(new BN(stakeIndex), new BN(lockPeriod))
+    .lockNft(new BN(stakeId), new BN(lockPeriod), null)
     .accounts({
       signer: signer.publicKey,
       mint,
@@ -37,16 +38,16 @@ export const lockNftRpc = async (
 };
 
 export const restakeRpc = async (
-  stakeIndex: number,
+  stakeId: number,
   signer: Keypair,
   mint: PublicKey,
   program: Program<ViridisStaking>
 ) => {
   await program.methods
-    .restake(new BN(stakeIndex))
+    .restake(new BN(stakeId))
     .accounts({
       signer: signer.publicKey,
-      mint,
+      rewardMint: mint,
     })
     .signers([signer])
     .rpc();
@@ -67,13 +68,13 @@ export const initializeStakeInfoRpc = async (
 };
 
 export const unlockNftRpc = async (
-  stakeIndex: number,
+  stakeId: number,
   signer: Keypair,
   nft: PublicKey,
   program: Program<ViridisStaking>
 ) => {
   return await program.methods
-    .unlockNft(new BN(stakeIndex))
+    .unlockNft(new BN(stakeId))
     .accounts({
       signer: signer.publicKey,
       mint: nft,
@@ -83,32 +84,33 @@ export const unlockNftRpc = async (
 };
 
 export const claimRpc = async (
-  stakeIndex: number,
+  stakeId: number,
   signer: Keypair,
   mint: PublicKey,
   program: Program<ViridisStaking>
 ) => {
   await program.methods
-    .claim(new BN(stakeIndex))
+    .claim(new BN(stakeId))
     .accounts({
       signer: signer.publicKey,
-      mint,
+      rewardMint: mint,
     })
     .signers([signer])
     .rpc();
 };
 
 export const destakeRpc = async (
-  stakeIndex: number,
+  stakeId: number,
   signer: Keypair,
   mint: PublicKey,
   program: Program<ViridisStaking>
 ) => {
   await program.methods
-    .destake(new BN(stakeIndex))
+    .destake(new BN(stakeId))
     .accounts({
       signer: signer.publicKey,
       mint,
+      rewardMint: mint,
     })
     .signers([signer])
     .rpc();
@@ -2,7 +2,9 @@ import { startAnchor, ProgramTestContext } from "solana-bankrun";
 import { BankrunProvider } from "anchor-bankrun";
 import {
   AccountInfo,
+  ComputeBudgetProgram,
   PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
   TransactionInstruction,
   TransactionMessage,
   VersionedTransaction,
@@ -12,6 +14,7 @@ import {
   airdropSol,
   createTokenAccountAndCredit,
   createToken,
+  decodeAccount,
   getTokenBalance,
   setSplToAccount,
   d,
@@ -23,6 +26,7 @@ import {
   setupAddresses,
   eq,
   expectErrorWitLog,
+  getStakeEntry,
 } from "./utils";
 import chai from "chai";
 import chaiAsPromised from "chai-as-promised";
@@ -47,6 +51,13 @@ import {
   stakeRpc,
   unlockNftRpc,
 } from "./rpc";
+import {
+  deserializeTokenRecord,
+  getTokenRecordAddress,
+  setProgrammableNft,
+} from "./metaplex";
+import { AccountState } from "@solana/spl-token";
+import { TokenState } from "@metaplex-foundation/mpl-token-metadata";
 import { TEST_NFT_ADDRESS_WRONG_COLLECTION } from "../const";
 
 chai.use(chaiAsPromised);
@@ -79,6 +90,7 @@ describe("staking program in the solana-bankrun simulation", () => {
       .accounts({
         signer: userA.publicKey,
         mint: mintKeypair.publicKey,
+        rewardMint: mintKeypair.publicKey,
         nftCollection: addresses.nftCollection,
       })
       .signers([userA])
@@ -91,7 +103,13 @@ describe("staking program in the solana-bankrun simulation", () => {
 
   async function fetchStakes(stakeInfoAddress: PublicKey) {
     const stakeInfo = await fetchStakeInfo(stakeInfoAddress);
-    return stakeInfo.stakes;
+    const stakeIds = [...Array(stakeInfo.nextStakeId.toNumber()).keys()];
+    const stakes = await program.account.stakeEntry.fetchMultiple(
+      stakeIds.map((stakeId) =>
+        getStakeEntry(stakeInfo.address, stakeId, program.programId)
+      )
+    );
+    return stakes.filter((stake) => stake !== null);
   }
 
   async function fetchNftInfo() {
@@ -104,7 +122,7 @@ describe("staking program in the solana-bankrun simulation", () => {
 
   const getStakeTokenInstruction = async (amountDecimals: bigint) => {
     return program.methods
-      .stake(new BN(amountDecimals))
+      .stake(new BN(amountDecimals), null)
       .accounts({
         signer: userA.publicKey,
         mint: mintKeypair.publicKey,
@@ -112,12 +130,13 @@ describe("staking program in the solana-bankrun simulation", () => {
       .instruction();
   };
 
-  const getDestakeTokenInstruction = async (stakeIndex: number) => {
+  const getDestakeTokenInstruction = async (stakeId: number) => {
     return program.methods
-      .destake(new BN(stakeIndex))
+      .destake(new BN(stakeId))
       .accounts({
         signer: userA.publicKey,
         mint: mintKeypair.publicKey,
+        rewardMint: mintKeypair.publicKey,
       })
       .instruction();
   };
@@ -133,11 +152,11 @@ describe("staking program in the solana-bankrun simulation", () => {
   };
 
   const getLockNftInstruction = async (
-    stakeIndex: number,
+    stakeId: number,
     lockPeriod: number
   ) => {
     return await program.methods
-      .lockNft(new BN(stakeIndex), new BN(lockPeriod))
+      .lockNft(new BN(stakeId), new BN(lockPeriod), null)
       .accounts({
         signer: userA.publicKey,
         mint: addresses.nft,
@@ -191,15 +210,14 @@ describe("staking program in the solana-bankrun simulation", () => {
 
   it("should successfully update config parameters", async () => {
     const updateArgs = {
-      admin: userB.publicKey,
-      baseLockDays: 0,
+      baseLockDays: 1,
       maxNftApyDurationDays: 92,
       baseApy: 500,
       maxNftRewardLamports: new BN(1_000_000),
       nftDaysApy: [
-        { days: 30, apy: 1000 },
-        { days: 60, apy: 2000 },
-        { days: 90, apy: 3000 },
+        { days: 30, apy: 1000, enabled: true },
+        { days: 60, apy: 2000, enabled: true },
+        { days: 90, apy: 3000, enabled: true },
       ],
     };
 
@@ -208,7 +226,7 @@ describe("staking program in the solana-bankrun simulation", () => {
       .updateConfig(updateArgs)
       .accounts({
         config: addresses.config,
-        admin: userA.publicKey,
+        configManager: userA.publicKey,
       })
       .signers([userA])
       .rpc();
@@ -220,6 +238,29 @@ describe("staking program in the solana-bankrun simulation", () => {
       updatedConfig,
       updateArgs
     );
+
+    // Tiers longer than the max NFT APY duration can never be locked
+    await expect(
+      program.methods
+        .updateConfig({ ...updateArgs, maxNftApyDurationDays: 60 })
+        .accounts({
+          config: addresses.config,
+          configManager: userA.publicKey,
+        })
+        .signers([userA])
+        .rpc()
+    ).to.be.rejectedWith(/NFT lock tier days exceed max NFT APY duration days/);
+
+    await expect(
+      program.methods
+        .updateConfig({ ...updateArgs, baseLockDays: 0 })
+        .accounts({
+          config: addresses.config,
+          configManager: userA.publicKey,
+        })
+        .signers([userA])
+        .rpc()
+    ).to.be.rejectedWith(/Base lock days must be greater than zero/);
   });
 
   it("should initialize stake info that holds owner information", async () => {
@@ -262,8 +303,10 @@ describe("staking program in the solana-bankrun simulation", () => {
     // Assert initial stake parameters
     expect(initialStake.stakeLockDays).to.equal(initialConfig.baseLockDays);
     expect(initialStake.baseApy).to.equal(initialConfig.baseApy);
-    expect(initialStake.nftLockDays).to.equal(initialConfig.nftDaysApy[0].days);
-    expect(initialStake.nftApy).to.equal(initialConfig.nftDaysApy[0].apy);
+    expect(initialStake.nfts[0].lockDays).to.equal(
+      initialConfig.nftDaysApy[0].days
+    );
+    expect(initialStake.nfts[0].apy).to.equal(initialConfig.nftDaysApy[0].apy);
     expect(
       initialStake.maxNftRewardLamports.eq(
         new BN(initialConfig.maxNftRewardLamports)
@@ -275,15 +318,14 @@ describe("staking program in the solana-bankrun simulation", () => {
 
     // Prepare update arguments
     const updateArgs = {
-      admin: null,
-      baseLockDays: 0, // New base lock days
+      baseLockDays: 1, // New base lock days
       maxNftApyDurationDays: 100, // New max NFT APY duration
       baseApy: 350, // New base APY (5%)
       maxNftRewardLamports: new BN(1_000_000), // New max NFT reward
       nftDaysApy: [
-        { days: 10, apy: 1000 }, // 10%
-        { days: 13, apy: 2000 }, // 20%
-        { days: 95, apy: 3000 }, // 30%
+        { days: 10, apy: 1000, enabled: true }, // 10%
+        { days: 13, apy: 2000, enabled: true }, // 20%
+        { days: 95, apy: 3000, enabled: true }, // 30%
       ],
     };
 
@@ -291,7 +333,7 @@ describe("staking program in the solana-bankrun simulation", () => {
     await program.methods
       .updateConfig(updateArgs)
       .accounts({
-        admin: userA.publicKey,
+        configManager: userA.publicKey,
       })
       .signers([userA])
       .rpc();
@@ -321,8 +363,8 @@ describe("staking program in the solana-bankrun simulation", () => {
     // Assert new stake parameters
     expect(newStake.stakeLockDays).to.equal(updatedConfig.baseLockDays);
     expect(newStake.baseApy).to.equal(updatedConfig.baseApy);
-    expect(newStake.nftLockDays).to.equal(updatedConfig.nftDaysApy[0].days);
-    expect(newStake.nftApy).to.equal(updatedConfig.nftDaysApy[0].apy);
+    expect(newStake.nfts[0].lockDays).to.equal(updatedConfig.nftDaysApy[0].days);
+    expect(newStake.nfts[0].apy).to.equal(updatedConfig.nftDaysApy[0].apy);
     expect(
       newStake.maxNftRewardLamports.eq(
         new BN(updatedConfig.maxNftRewardLamports)
@@ -368,10 +410,13 @@ describe("staking program in the solana-bankrun simulation", () => {
     );
 
     // Assert newest stake parameters
-    expect(newestStake.nftLockDays).to.equal(updatedConfig.nftDaysApy[1].days);
-    expect(newestStake.nftApy).to.equal(updatedConfig.nftDaysApy[1].apy);
+    expect(newestStake.nfts[0].lockDays).to.equal(
+      updatedConfig.nftDaysApy[1].days
+    );
+    expect(newestStake.nfts[0].apy).to.equal(updatedConfig.nftDaysApy[1].apy);
 
     await stakeRpc(d(50_000), userA, mintKeypair.publicKey, program);
+    await simulateTimePassage(ONE_DAY_SECONDS, context);
     await destakeRpc(3, userA, mintKeypair.publicKey, program);
 
     await simulateTimePassage(ONE_DAY_SECONDS * 10, context);
@@ -384,6 +429,7 @@ describe("staking program in the solana-bankrun simulation", () => {
       baseLockDays,
       maxNftRewardLamports,
       maxNftApyDurationDays,
+      rewardRate,
     } = await fetchConfig();
 
     const userTokens = d(1_000_000);
@@ -436,20 +482,28 @@ describe("staking program in the solana-bankrun simulation", () => {
     ).true;
 
     const expectedStakeAfterStaking: StakeEntry = {
+      id: new BN(0),
       amount: new BN(userTokens),
       startTime: new BN(clockBeforeStaking.unixTimestamp),
       stakeLockDays: baseLockDays,
       baseApy,
-      nft: addresses.nft,
-      nftLockTime: stake.startTime,
-      nftLockDays: nftLockPeriod,
-      nftApy: nftAPY,
-      nftUnlockTime: null,
+      nfts: [
+        {
+          nft: addresses.nft,
+          lockTime: stake.startTime,
+          lockDays: nftLockPeriod,
+          apy: nftAPY,
+          unlockTime: null,
+          maxRewardLamports: maxNftRewardLamports,
+          maxApyDurationDays: maxNftApyDurationDays,
+        },
+      ],
       destakeTime: null,
       restakeTime: null,
-      parentStakeIndex: null,
+      parentStakeId: null,
       paidAmount: new BN(0),
       maxNftApyDurationDays: maxNftApyDurationDays,
+      rewardRate,
       maxNftRewardLamports: maxNftRewardLamports,
     };
 
@@ -542,7 +596,10 @@ describe("staking program in the solana-bankrun simulation", () => {
     ).true;
 
     expect(
-      eq(stakeAfterNftUnlock.nftUnlockTime, clockAfterDestake.unixTimestamp),
+      eq(
+        stakeAfterNftUnlock.nfts[0].unlockTime,
+        clockAfterDestake.unixTimestamp
+      ),
       "stake unlock time should equal current block timestamp"
     ).true;
 
@@ -1520,4 +1577,147 @@ describe("staking program in the solana-bankrun simulation", () => {
       )
     ).true;
   });
+  it("should lock and unlock a programmable NFT through its token records", async () => {
+    await creditSpl(d(1_000_000), userA.publicKey);
+    await creditVault(d(10_000_000));
+    const pnft = setProgrammableNft(
+      context,
+      userA.publicKey,
+      addresses.nftCollection
+    );
+
+    await initializeStakeInfoRpc(userA, program);
+    await stakeRpc(d(10_000), userA, mintKeypair.publicKey, program);
+
+    const [nftLockAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("nft"), pnft.mint.toBuffer()],
+      program.programId
+    );
+    const [lockTokenRecord] = getTokenRecordAddress(pnft.mint, nftLockAccount);
+    const programmableAccounts = {
+      signer: userA.publicKey,
+      mint: pnft.mint,
+      edition: pnft.edition,
+      userTokenRecord: pnft.ownerTokenRecord,
+      lockTokenRecord,
+      authorizationRules: null,
+      authorizationRulesProgram: null,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    };
+    const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+      units: 400_000,
+    });
+
+    await expectErrorWitLog(
+      program.methods
+        .lockNft(new BN(0), new BN(30), null)
+        .accountsPartial({
+          ...programmableAccounts,
+          edition: null,
+          nftCollection: null,
+          creatorAllowList: null,
+        })
+        .preInstructions([computeBudget])
+        .signers([userA])
+        .rpc(),
+      "Programmable NFT transfers need the edition"
+    );
+
+    await program.methods
+      .lockNft(new BN(0), new BN(30), null)
+      .accountsPartial({
+        ...programmableAccounts,
+        nftCollection: null,
+        creatorAllowList: null,
+      })
+      .preInstructions([computeBudget])
+      .signers([userA])
+      .rpc();
+
+    const lockedAccount = await decodeAccount(
+      context,
+      nftLockAccount,
+      "account"
+    );
+    expect(lockedAccount.amount).to.equal(1n);
+    expect(lockedAccount.owner.equals(nftLockAccount)).true;
+    expect(lockedAccount.state).to.equal(AccountState.Frozen);
+    expect(await getBalance(pnft.ownerToken)).to.equal(0n);
+
+    const lockRecordInfo = await context.banksClient.getAccount(
+      lockTokenRecord
+    );
+    expect(lockRecordInfo).not.null;
+    expect(deserializeTokenRecord(lockRecordInfo!.data).state).to.equal(
+      TokenState.Unlocked
+    );
+
+    const [lockedStake] = await fetchStakes(addresses.stakeInfo);
+    expect(lockedStake.nfts[0].nft.equals(pnft.mint)).true;
+
+    await simulateTimePassage(ONE_DAY_SECONDS * 30, context);
+    await destakeRpc(0, userA, mintKeypair.publicKey, program);
+
+    await program.methods
+      .unlockNft(new BN(0))
+      .accountsPartial(programmableAccounts)
+      .preInstructions([computeBudget])
+      .signers([userA])
+      .rpc();
+
+    const userAccount = await decodeAccount(
+      context,
+      pnft.ownerToken,
+      "account"
+    );
+    expect(userAccount.amount).to.equal(1n);
+    expect(userAccount.owner.equals(userA.publicKey)).true;
+    expect(userAccount.state).to.equal(AccountState.Frozen);
+    expect(await getBalance(nftLockAccount)).to.equal(0n);
+
+    const userRecordInfo = await context.banksClient.getAccount(
+      pnft.ownerTokenRecord
+    );
+    expect(userRecordInfo).not.null;
+    expect(deserializeTokenRecord(userRecordInfo!.data).state).to.equal(
+      TokenState.Unlocked
+    );
+
+    const [unlockedStake] = await fetchStakes(addresses.stakeInfo);
+    expect(unlockedStake.nfts[0].unlockTime).not.null;
+  });
+  it("should keep counting stake ids after the stake info is closed", async () => {
+    await creditSpl(d(1_000_000), userA.publicKey);
+    await creditVault(d(10_000_000));
+
+    await initializeStakeInfoRpc(userA, program);
+    await stakeRpc(d(10_000), userA, mintKeypair.publicKey, program);
+    await simulateTimePassage(ONE_YEAR_SECONDS, context);
+    await destakeRpc(0, userA, mintKeypair.publicKey, program);
+
+    await program.methods
+      .closeStakeEntry(new BN(0))
+      .accounts({ signer: userA.publicKey })
+      .signers([userA])
+      .rpc();
+    await program.methods
+      .closeStakeInfo()
+      .accountsPartial({
+        signer: userA.publicKey,
+        stakeAccount: addresses.userStake,
+      })
+      .signers([userA])
+      .rpc();
+
+    expect(await context.banksClient.getAccount(addresses.stakeInfo)).null;
+    expect(await context.banksClient.getAccount(addresses.userStake)).null;
+
+    await initializeStakeInfoRpc(userA, program);
+    await stakeRpc(d(10_000), userA, mintKeypair.publicKey, program);
+
+    const stakeInfo = await fetchStakeInfo(addresses.stakeInfo);
+    expect(stakeInfo.nextStakeId.toNumber()).to.equal(2);
+    const [stake] = await fetchStakes(addresses.stakeInfo);
+    expect(stake.id.toNumber()).to.equal(1);
+  });
 });
@@ -1,20 +1,28 @@
 import { BN } from "@coral-xyz/anchor";
 import { PublicKey } from "@solana/web3.js";
 
+export type LockedNft = {
+  nft: PublicKey;
+  lockTime: BN;
+  lockDays: number;
+  apy: number;
+  unlockTime: BN | null;
+  maxRewardLamports: BN;
+  maxApyDurationDays: number;
+};
+
 export type StakeEntry = {
+  id: BN;
   amount: BN;
   startTime: BN;
   stakeLockDays: number;
   baseApy: number;
-  nft: PublicKey | null;
-  nftLockTime: BN | null;
-  nftLockDays: number | null;
-  nftApy: number | null;
-  nftUnlockTime: BN | null;
+  nfts: LockedNft[];
   restakeTime: BN | null;
   destakeTime: BN | null;
   paidAmount: BN;
   maxNftRewardLamports: BN;
   maxNftApyDurationDays: BN;
-  parentStakeIndex: BN | null;
+  rewardRate: BN;
+  parentStakeId: BN | null;
 };
@@ -1,6 +1,7 @@
 import {
   ACCOUNT_SIZE,
   AccountLayout,
+  AccountState,
   MINT_SIZE,
   RawAccount,
   MintLayout,
@@ -96,7 +97,8 @@ export async function setSplToAccount(
   mint: PublicKey,
   owner: PublicKey,
   ata: PublicKey,
-  amount: bigint
+  amount: bigint,
+  state: AccountState = AccountState.Initialized
 ) {
   const tokenAccData = Buffer.alloc(ACCOUNT_SIZE);
   AccountLayout.encode(
@@ -107,7 +109,7 @@ export async function setSplToAccount(
       delegateOption: 0,
       delegate: PublicKey.default,
       delegatedAmount: 0n,
-      state: 1,
+      state,
       isNativeOption: 0,
       isNative: 0n,
       closeAuthorityOption: 0,
@@ -227,6 +229,20 @@ export const getStakeInfo = (address: PublicKey, programId: PublicKey) =>
     programId
   )[0];
 
+export const getStakeEntry = (
+  address: PublicKey,
+  stakeId: number,
+  programId: PublicKey
+) =>
+  PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("stake"),
+      address.toBuffer(),
+      new BN(stakeId).toArrayLike(Buffer, "le", 8),
+    ],
+    programId
+  )[0];
+
 export const d = (amount: number): bigint => BigInt(amount * 10 ** DECIMALS);
 
 export function assertDeepEqual<T extends Record<string, any>>(